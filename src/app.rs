@@ -6,87 +6,80 @@ pub struct App {
     is_running          : bool,
     hotkey_manager      : Option<global_hotkey::GlobalHotKeyManager>,
     texture_packer      : crate::TexturePacker,
+    font                : crate::Font,
+    atlas_debug_view    : crate::AtlasDebugView,
+    cursor_position     : (f32, f32),     // in logical pixels, origin at the bottom left like the renderer
+    needs_redraw        : bool,
+    occluded            : bool,
+    last_frame          : Option<std::time::Instant>,
+    frame_interval      : std::time::Duration,
+    surface_recreated_at: Option<std::time::Instant>,
+    gpu_recreated_at    : Option<std::time::Instant>,
 }
 
-impl App {
+// sent to the event loop from other threads, which also wakes it up
+#[derive(Debug)]
+pub enum AppEvent {
+    HotKey(global_hotkey::GlobalHotKeyEvent),
+}
 
-    pub async fn new(event_loop: &winit::event_loop::EventLoop<()>) -> Result<Self, String> {
-        let config = crate::ConfigStore::new(
-            dirs::home_dir().ok_or("Failed to get home dir!")?.join(".xettacast/app_config.yml").to_str().ok_or("Failed to join paths!")?.to_string(),
-            Some(include_str!("./assets/config/default_app.yaml").to_string())).await?;
+// used when the monitor does not report its refresh rate
+const DEFAULT_FRAME_INTERVAL: std::time::Duration = std::time::Duration::from_micros(16_667);
+// how often debug builds wake up while idle to look for shader changes, see GraphicsPipeline::reload_if_changed
+const SHADER_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+// a surface lost again this soon after being recreated means the device itself is gone
+const SURFACE_LOST_GRACE: std::time::Duration = std::time::Duration::from_secs(1);
+// minimum time between two device recreations, frames in between are skipped
+const GPU_RECREATE_BACKOFF: std::time::Duration = std::time::Duration::from_secs(2);
 
-        let window = crate::Window::new(event_loop).await?;
-        let instance = crate::GPUInstance::new(&window).await?;
+impl App {
 
-        let renderer = crate::Renderer::new(&instance, instance.swapchain().ok_or("No Swapchain!")?.surface_config().format).await?;
+    pub async fn new(event_loop: &winit::event_loop::EventLoop<AppEvent>, command_line: crate::CommandLine) -> Result<Self, String> {
+        let config = Self::load_config().await?;
 
-        let mut packer = crate::TexturePacker::new(4096, 4096, 2).await?;
+        let window = crate::Window::new(event_loop, true).await?;
+        let instance = crate::GPUInstance::new(&window, Self::gpu_options(&config, &command_line)).await?;
 
-        
+        // older configs do not have the key yet
+        let msaa_samples = match config.get("msaa_samples") {
+            Ok(crate::AppConfigItem::MsaaSamples(samples)) => samples,
+            Ok(_) => 1,
+            Err(e) => {
+                log::warn!("Using msaa_samples 1: {}", e);
+                1
+            }
+        };
+        let mut packer = crate::TexturePacker::new(4096, 4096, 2, crate::TexturePackerFormat::R8).await?;
 
         let bytes0 = include_bytes!("./assets/fonts/OpenSans/OpenSans-Regular.ttf");
         let bytes1 = include_bytes!("./assets/fonts/Icons/MaterialDesignIconsDesktop.ttf");
-        let font0 = crate::Font::new(bytes0, 50.0f32).await.unwrap();
-        let font1 = crate::Font::new(bytes1, 50.0f32).await.unwrap();
+        let font_scale = 50.0f32;
 
+        let cache_dir = dirs::home_dir().ok_or("Failed to get home dir!")?.join(".xettacast/cache").to_str().ok_or("Failed to join paths!")?.to_string();
+        let cache_key = packer.cache_key(&[bytes0, bytes1, &font_scale.to_le_bytes()]);
 
-        // font.save_bitmaps("p").unwrap();
-
-        font0.glyphs().iter().for_each(|(id, glyph)| {
-            if glyph.width == 0 || glyph.height == 0 {
-                log::warn!("Empty glyph: {} {}", id, *id as u32);
-                return;
-            }
-            packer.add(&format!("glyph0{}", id), glyph.width, glyph.height);
-        });
-
-        font1.glyphs().iter().for_each(|(id, glyph)| {
-            if glyph.width == 0 || glyph.height == 0 {
-                log::warn!("Empty glyph: {} {}", id, *id as u32);
-                return;
-            }
-            packer.add(&format!("glyph1{}", id), glyph.width, glyph.height);
-        });
-
-        // packer.add("asds", 10, 5);
-        
-        log::info!("Packing : {:?}", packer.pack());
-
-        font0.glyphs().iter().for_each(|(id, glyph)| {
-            let bitmap = &glyph.bitmap;
-            // create a 4 channel bitmap
-            let mut data = vec![0u8; bitmap.len() * 4];
-            for i in 0..bitmap.len() {
-                data[i * 4] = 255;
-                data[i * 4 + 1] = 255;
-                data[i * 4 + 2] = 255;
-                data[i * 4 + 3] = bitmap[i];
-            }
-            packer.update(&format!("glyph0{}", id), &data, glyph.width, glyph.height);
-        });
-
-        font1.glyphs().iter().for_each(|(id, glyph)| {
-            let bitmap = &glyph.bitmap;
-            // create a 4 channel bitmap
-            let mut data = vec![0u8; bitmap.len() * 4];
-            for i in 0..bitmap.len() {
-                data[i * 4] = 255;
-                data[i * 4 + 1] = 255;
-                data[i * 4 + 2] = 255;
-                data[i * 4 + 3] = bitmap[i];
+        // only the first font is kept around, for its metrics
+        let font = match crate::TexturePacker::from_cache(&cache_dir, cache_key).await {
+            Ok(cached) => {
+                packer = cached;
+                crate::Font::new_metrics_only(bytes0, font_scale).await?
+            },
+            Err(e) => {
+                log::info!("Texture packer cache not used: {}", e);
+                let font0 = crate::Font::new(bytes0, font_scale).await?;
+                let font1 = crate::Font::new(bytes1, font_scale).await?;
+                if Self::pack_glyphs(&mut packer, &[&font0, &font1])? {
+                    if let Err(e) = packer.save_cache(&cache_dir, cache_key) {
+                        log::warn!("Failed to save texture packer cache: {}", e);
+                    }
+                } else {
+                    log::warn!("Not caching a partially packed atlas");
+                }
+                font0
             }
-            packer.update(&format!("glyph1{}", id), &data, glyph.width, glyph.height);
-        });
-
-        log::info!("Updated all glyphs");
-
-//        packer.fill_color()?;
-        packer.save(&format!("p/packed{}.png", 0), 0)?;
-        packer.save(&format!("p/packed{}.png", 1), 1)?;
+        };
 
-        packer.fill_color_empty()?;
-        packer.save(&format!("p/empty{}.png", 0), 0)?;
-        packer.save(&format!("p/empty{}.png", 1), 1)?;
+        let renderer = Self::create_renderer(&instance, &packer, msaa_samples, window.scale_factor() as f32).await?;
 
         let mut obj = Self {
             config              : config,
@@ -96,7 +89,17 @@ impl App {
             is_running          : true,
             hotkey_manager      : None,
             texture_packer      : packer,
+            font                : font,
+            atlas_debug_view    : crate::AtlasDebugView::new(command_line.debug_atlas),
+            cursor_position     : (0.0, 0.0),
+            needs_redraw        : true,
+            occluded            : false,
+            last_frame          : None,
+            frame_interval      : DEFAULT_FRAME_INTERVAL,
+            surface_recreated_at: None,
+            gpu_recreated_at    : None,
         };
+        obj.update_frame_interval();
         
         if let crate::AppConfigItem::Monitor(monitor) = obj.config.get("monitor")? {
             if let Some(monitor) = obj.window.find_monitor(&monitor) {
@@ -108,38 +111,56 @@ impl App {
             }
         }
         
+        Self::forward_hotkey_events(event_loop.create_proxy());
         obj.setup_hotkey_manager()?;
 
         Ok(obj)
     }
 
+    // nothing is rendered while the window is hidden, fully covered or minimized. frames that could not be
+    // presented keep needs_redraw set and are retried on the next tick
     pub fn on_update(&mut self) -> Result<bool, String> {
-        self.on_render()?;
+        if self.can_render() {
+            if self.on_render()? {
+                self.needs_redraw = false;
+            }
+            self.last_frame = Some(std::time::Instant::now());
+        }
         Ok(self.is_running)
     }
 
-    pub fn on_render(&mut self) -> Result<(), String> {
+    // requests a redraw once one is needed and the frame interval has passed, otherwise tells the loop how long to sleep
+    pub fn on_main_events_cleared(&mut self) -> winit::event_loop::ControlFlow {
+        let now = std::time::Instant::now();
+        if !self.needs_redraw || !self.can_render() {
+            return Self::idle_control_flow(now);
+        }
 
-        let instance = &self.gpu_instance;
-        let swapchain = instance.swapchain().ok_or("No swapchain!")?;
+        let next_frame = self.last_frame.map(|last_frame| last_frame + self.frame_interval).unwrap_or(now);
+        if next_frame <= now {
+            self.window.request_redraw();
+            Self::idle_control_flow(now)
+        } else {
+            winit::event_loop::ControlFlow::WaitUntil(next_frame)
+        }
+    }
 
+    // returns whether a frame was presented
+    pub fn on_render(&mut self) -> Result<bool, String> {
 
-        let surface_texture: wgpu::SurfaceTexture = match swapchain.acquire_texture(&self.gpu_instance) {
-            Ok(surface_texture) => {surface_texture},
-            Err(wgpu::SurfaceError::Lost) => {
-                instance.configure_surface(swapchain.surface_config());
-                return Ok(());
-            },
-            Err(e) => {
-                log::error!("Failed to acquire texture: {}", e);
-                return Ok(());
-            }
-        }.into();
+        let surface_texture = match self.acquire_surface_texture()? {
+            Some(surface_texture) => surface_texture,
+            None => return Ok(false),
+        };
+
+        let instance = &self.gpu_instance;
+        let swapchain = instance.swapchain().ok_or("No swapchain!")?;
+        let frame_res = (swapchain.surface_config().width, swapchain.surface_config().height);
 
         let view = surface_texture.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         self.renderer.begin()?;
-        self.renderer.set_frame_res(self.window.get_size());
+        self.renderer.set_frame_res(frame_res);
         self.renderer.set_target_view(view);
 
 
@@ -184,6 +205,9 @@ impl App {
         //     }
         // }
 
+        let logical_size = self.renderer.logical_size();
+        self.atlas_debug_view.render(&mut self.renderer, &self.texture_packer, &self.font, "glyph0", logical_size);
+
         
         self.renderer.end(&instance)?;
         
@@ -192,11 +216,107 @@ impl App {
 
 
 
-        Ok(())
+        Ok(true)
+    }
+
+    // None when this frame has to be skipped, the surface is fixed up so the next one can be drawn
+    fn acquire_surface_texture(&mut self) -> Result<Option<wgpu::SurfaceTexture>, String> {
+        let swapchain = self.gpu_instance.swapchain().ok_or("No swapchain!")?;
+
+        match swapchain.acquire_texture(&self.gpu_instance) {
+            Ok(surface_texture) => Ok(Some(surface_texture)),
+            Err(wgpu::SurfaceError::Timeout) => {
+                log::warn!("Timed out acquiring the surface texture, skipping the frame");
+                Ok(None)
+            },
+            Err(wgpu::SurfaceError::Outdated) => {
+                log::info!("Surface outdated, reconfiguring");
+                self.reconfigure_swapchain();
+                Ok(None)
+            },
+            Err(wgpu::SurfaceError::Lost) => {
+                let recently_recreated = self.surface_recreated_at.map(|at| at.elapsed() < SURFACE_LOST_GRACE).unwrap_or(false);
+                if recently_recreated {
+                    self.recreate_gpu();
+                } else {
+                    log::warn!("Surface lost, recreating it");
+                    if let Err(e) = self.gpu_instance.recreate_surface(&self.window) {
+                        log::error!("{}", e);
+                        self.recreate_gpu();
+                    }
+                }
+                self.surface_recreated_at = Some(std::time::Instant::now());
+                Ok(None)
+            },
+            Err(wgpu::SurfaceError::OutOfMemory) => {
+                // everything allocated on the old device goes away with it, which is the best chance to recover
+                log::error!("Out of memory acquiring the surface texture");
+                self.recreate_gpu();
+                Ok(None)
+            },
+        }
+    }
+
+    // matches the swapchain to the current window size
+    fn reconfigure_swapchain(&mut self) {
+        let (width, height) = self.window.get_size();
+        if let Some(swapchain) = self.gpu_instance.swapchain_mut() {
+            swapchain.resize(width, height);
+        }
+        self.gpu_instance.reconfigure_surface();
+    }
+
+    // replaces the device, surface and everything created on them, at most once per GPU_RECREATE_BACKOFF.
+    // the old instance is only replaced once the new one and its renderer exist, on failure it is kept (and tried
+    // again later). its surface has to be released up front though, a window can only have one swapchain at a time
+    fn recreate_gpu(&mut self) {
+        if self.gpu_recreated_at.map(|at| at.elapsed() < GPU_RECREATE_BACKOFF).unwrap_or(false) {
+            return;
+        }
+        self.gpu_recreated_at = Some(std::time::Instant::now());
+
+        log::warn!("Recreating the gpu device");
+        self.gpu_instance.release_surface();
+
+        match self.create_gpu() {
+            Ok((instance, renderer)) => {
+                self.renderer = renderer;
+                self.gpu_instance = instance;
+                self.needs_redraw = true;
+            },
+            Err(e) => {
+                log::error!("Failed to recreate the gpu device, keeping the old one: {}", e);
+                if let Err(e) = self.gpu_instance.recreate_surface(&self.window) {
+                    log::error!("{}", e);
+                }
+            }
+        }
+    }
+
+    fn create_gpu(&self) -> Result<(crate::GPUInstance, crate::Renderer), String> {
+        let instance = pollster::block_on(crate::GPUInstance::new(&self.window, self.gpu_instance.options().clone()))?;
+        let renderer = pollster::block_on(Self::create_renderer(&instance, &self.texture_packer, self.renderer.sample_count(), self.renderer.scale_factor()))?;
+        Ok((instance, renderer))
+    }
+
+    fn can_render(&self) -> bool {
+        let zero_sized = self.gpu_instance.swapchain().map(|swapchain| swapchain.is_zero_sized()).unwrap_or(true);
+        self.window.is_visible() && !self.occluded && !zero_sized
     }
     
+    // sleeps until the next event, debug builds wake up now and then to hot reload shaders
+    fn idle_control_flow(now: std::time::Instant) -> winit::event_loop::ControlFlow {
+        if cfg!(debug_assertions) {
+            winit::event_loop::ControlFlow::WaitUntil(now + SHADER_POLL_INTERVAL)
+        } else {
+            winit::event_loop::ControlFlow::Wait
+        }
+    }
+
     pub fn global_update(&mut self) -> Result<(), String> {
-        self.check_hotkey()?;
+        if self.renderer.reload_shaders(&self.gpu_instance) {
+            self.needs_redraw = true;
+        }
 
         Ok(())
     }
@@ -209,15 +329,51 @@ impl App {
             winit::event::WindowEvent::Resized(size) => {
                 self.gpu_instance.swapchain_mut().unwrap().resize(size.width, size.height);
                 self.gpu_instance.reconfigure_surface();
+                self.needs_redraw = true;
                 log::info!("Resized: {:?}", size);
             },
+            winit::event::WindowEvent::ScaleFactorChanged { scale_factor, new_inner_size } => {
+                self.renderer.set_scale_factor(scale_factor as f32);
+                self.gpu_instance.swapchain_mut().unwrap().resize(new_inner_size.width, new_inner_size.height);
+                self.gpu_instance.reconfigure_surface();
+                self.update_frame_interval();
+                self.needs_redraw = true;
+                log::info!("Scale factor changed: {} ({:?})", scale_factor, new_inner_size);
+            },
+            winit::event::WindowEvent::CursorMoved { position, .. } => {
+                // the renderer has its origin at the bottom left
+                let scale_factor = self.window.scale_factor() as f32;
+                self.cursor_position = (position.x as f32 / scale_factor, (self.window.height() as f32 - position.y as f32) / scale_factor);
+                self.atlas_debug_view.set_cursor(self.cursor_position.0, self.cursor_position.1);
+                // only the debug view follows the cursor so far
+                if self.atlas_debug_view.is_visible() {
+                    self.needs_redraw = true;
+                }
+            },
+            winit::event::WindowEvent::Moved(_) => {
+                // the window may have moved to a monitor with a different refresh rate
+                self.update_frame_interval();
+            },
+            winit::event::WindowEvent::Occluded(occluded) => {
+                self.occluded = occluded;
+                if !occluded {
+                    self.needs_redraw = true;
+                }
+            },
+            winit::event::WindowEvent::KeyboardInput { input, .. } => {
+                if input.state == winit::event::ElementState::Pressed && input.virtual_keycode == Some(winit::event::VirtualKeyCode::F12) {
+                    self.atlas_debug_view.toggle();
+                    self.needs_redraw = true;
+                }
+            },
             _ => {}
         }
     
     }
 
-    pub fn request_redraw(&self) {
-        self.window.request_redraw();
+    // the frame is drawn on the next due tick, see on_main_events_cleared
+    pub fn request_redraw(&mut self) {
+        self.needs_redraw = true;
     }
 
  
@@ -225,19 +381,96 @@ impl App {
         self.is_running = false;
     }
 
-    fn check_hotkey(&mut self) -> Result<(), String> {
-        if let Ok(key) = global_hotkey::GlobalHotKeyEvent::receiver().try_recv() {
-            if key.state == global_hotkey::HotKeyState::Pressed {
-                // log::info!("{:?}", key);
-                if self.window.is_visible() {
-                    self.window.hide();
-                }else {
-                    self.window.show();
+    pub fn is_running(&self) -> bool {
+        self.is_running
+    }
+
+    pub fn on_app_event(&mut self, event: AppEvent) {
+        match event {
+            AppEvent::HotKey(key) => {
+                if key.state == global_hotkey::HotKeyState::Pressed {
+                    // log::info!("{:?}", key);
+                    if self.window.is_visible() {
+                        self.window.hide();
+                    }else {
+                        self.window.show();
+                        self.needs_redraw = true;
+                    }
                 }
+            },
+        }
+    }
+
+    // the hotkey channel does not wake the event loop, so the events are sent through it instead.
+    // the handler can only be set once per process
+    fn forward_hotkey_events(proxy: winit::event_loop::EventLoopProxy<AppEvent>) {
+        let proxy = std::sync::Mutex::new(proxy);
+        global_hotkey::GlobalHotKeyEvent::set_event_handler(Some(move |event| {
+            if let Ok(proxy) = proxy.lock() {
+                // only fails once the event loop is gone
+                let _ = proxy.send_event(AppEvent::HotKey(event));
+            }
+        }));
+    }
+
+    // the gpu-info command, the window is only created for its surface and stays hidden
+    pub async fn gpu_info(event_loop: &winit::event_loop::EventLoop<AppEvent>, command_line: &crate::CommandLine) -> Result<String, String> {
+        let config = Self::load_config().await?;
+        // the surface only needs a window, it is never shown
+        let window = crate::Window::new(event_loop, false).await?;
+        crate::GPUInstance::describe_adapters(&window, &Self::gpu_options(&config, command_line)).await
+    }
 
+    async fn load_config() -> Result<crate::ConfigStore, String> {
+        crate::ConfigStore::new(
+            dirs::home_dir().ok_or("Failed to get home dir!")?.join(".xettacast/app_config.yml").to_str().ok_or("Failed to join paths!")?.to_string(),
+            Some(include_str!("./assets/config/default_app.yaml").to_string())).await
+    }
+
+    // command line flags win over the config, keys missing from older configs keep the defaults
+    fn gpu_options(config: &crate::ConfigStore, command_line: &crate::CommandLine) -> crate::GPUInstanceOptions {
+        let mut options = crate::GPUInstanceOptions::default();
+
+        for key in ["present_mode", "backend", "power_preference", "fallback_adapter"] {
+            match config.get(key) {
+                Ok(crate::AppConfigItem::PresentMode(present_mode)) => options.present_mode = present_mode,
+                Ok(crate::AppConfigItem::Backend(backends)) => options.backends = backends,
+                Ok(crate::AppConfigItem::PowerPreference(power_preference)) => options.power_preference = power_preference,
+                Ok(crate::AppConfigItem::FallbackAdapter(fallback_adapter)) => options.force_fallback_adapter = fallback_adapter,
+                Ok(_) => {},
+                Err(e) => log::warn!("Using the default {}: {}", key, e),
             }
         }
-        Ok(())
+
+        if let Some(present_mode) = command_line.present_mode {
+            options.present_mode = present_mode;
+        }
+        if let Some(backends) = command_line.backend {
+            options.backends = backends;
+        }
+        if let Some(power_preference) = command_line.power_preference {
+            options.power_preference = power_preference;
+        }
+        if command_line.fallback_adapter {
+            options.force_fallback_adapter = true;
+        }
+
+        options
+    }
+
+    async fn create_renderer(instance: &crate::GPUInstance, packer: &crate::TexturePacker, msaa_samples: u32, scale_factor: f32) -> Result<crate::Renderer, String> {
+        let format = instance.swapchain().ok_or("No Swapchain!")?.surface_config().format;
+        let mut renderer = crate::Renderer::new(instance, format, msaa_samples).await?;
+        renderer.upload_atlas(instance, packer).await?;
+        renderer.set_scale_factor(scale_factor);
+        Ok(renderer)
+    }
+
+    fn update_frame_interval(&mut self) {
+        self.frame_interval = match self.window.refresh_rate_millihertz() {
+            Some(millihertz) if millihertz > 0 => std::time::Duration::from_nanos(1_000_000_000_000 / millihertz as u64),
+            _ => DEFAULT_FRAME_INTERVAL,
+        };
     }
 
     fn setup_hotkey_manager(&mut self) -> Result<(), String> {
@@ -249,4 +482,41 @@ impl App {
         }
         Ok(())
     }
+
+    // returns whether every glyph fit, a partial atlas is still usable but should not be cached
+    fn pack_glyphs(packer: &mut crate::TexturePacker, fonts: &[&crate::Font]) -> Result<bool, String> {
+        for (index, font) in fonts.iter().enumerate() {
+            font.glyphs().iter().for_each(|(id, glyph)| {
+                if glyph.width == 0 || glyph.height == 0 {
+                    log::warn!("Empty glyph: {} {}", id, *id as u32);
+                    return;
+                }
+                packer.add(&format!("glyph{}{}", index, id), glyph.width, glyph.height);
+            });
+        }
+
+        let complete = match packer.pack() {
+            Ok(()) => true,
+            Err(e) => {
+                log::error!("Packing glyphs failed: {}", e);
+                false
+            }
+        };
+
+        for (index, font) in fonts.iter().enumerate() {
+            for (id, glyph) in font.glyphs().iter() {
+                // empty glyphs were never added, and anything that failed to pack was logged above
+                let name = format!("glyph{}{}", index, id);
+                if packer.region(&name).is_none() {
+                    continue;
+                }
+                // the packer stores single channel coverage, so the bitmap goes in as is
+                packer.update(&name, &glyph.bitmap, glyph.width, glyph.height)?;
+            }
+        }
+
+        log::info!("Updated all glyphs");
+
+        Ok(complete)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,68 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv             : vec2<f32>,
+};
+
+struct FragmentOutput {
+    @location(0) color0: vec4<f32>
+};
+
+struct BlurPassGPUData {
+    data0           : vec4<f32>   // (step_x, step_y) one texel along the blur axis in uv, radius in pixels, unused
+};
+
+@group(0) @binding(0) var<uniform> blurPassGPUData : BlurPassGPUData;
+
+@group(1) @binding(0) var sourceTexture : texture_2d<f32>;
+@group(1) @binding(1) var sourceSampler : sampler;
+
+const MAX_TAPS : i32 = 64;     // BlurPass::MAX_RADIUS
+
+// a single triangle covering the target, uv (0, 0) is the top left
+@vertex
+fn vs_main(
+    @builtin(vertex_index) index: u32
+) -> VertexOutput {
+    var output: VertexOutput;
+
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    output.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    output.uv = uv;
+
+    return output;
+}
+
+// one axis of a separable gaussian, the source is premultiplied so transparent texels do not darken the result
+@fragment
+fn fs_blur(
+    in: VertexOutput
+) -> FragmentOutput {
+    var output: FragmentOutput;
+
+    let step = blurPassGPUData.data0.xy;
+    let radius = blurPassGPUData.data0.z;
+    let sigma = max(radius * 0.5, 0.0001);
+    let taps = min(i32(ceil(radius)), MAX_TAPS);
+
+    var sum = textureSampleLevel(sourceTexture, sourceSampler, in.uv, 0.0);
+    var weight_sum = 1.0;
+    for (var i = 1; i <= taps; i++) {
+        let offset = f32(i);
+        let weight = exp(-(offset * offset) / (2.0 * sigma * sigma));
+        sum += textureSampleLevel(sourceTexture, sourceSampler, in.uv + step * offset, 0.0) * weight;
+        sum += textureSampleLevel(sourceTexture, sourceSampler, in.uv - step * offset, 0.0) * weight;
+        weight_sum += weight * 2.0;
+    }
+
+    output.color0 = sum / weight_sum;
+    return output;
+}
+
+@fragment
+fn fs_blit(
+    in: VertexOutput
+) -> FragmentOutput {
+    var output: FragmentOutput;
+    output.color0 = textureSampleLevel(sourceTexture, sourceSampler, in.uv, 0.0);
+    return output;
+}
@@ -3,6 +3,8 @@ struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) instance_id    : u32,
     @location(1) position_raw   : vec4<f32>,
+    @location(2) uv             : vec2<f32>,
+    @location(3) position_local : vec2<f32>,    // before rotation and transform, for the item shape
 };
 
 struct FragmentOutput {
@@ -10,22 +12,49 @@ struct FragmentOutput {
 };
 
 struct RendererGPUData {
-    data0           : vec4<f32>   // aspect_ratio, unused, unused, unused
+    data0           : vec4<f32>   // scale_factor, frame_width, frame_height, unused
 };
 
 struct RendererItem {
     offset_scale            : vec4<f32>,    // (x, y), (scale_x, scale_y)
-    texture_transform       : vec4<f32>,    // (x, y), (scale_x, scale_y) / color (r, g, b, a)
+    texture_transform       : vec4<f32>,    // (x, y), (scale_x, scale_y)
+    color                   : vec4<f32>,    // (r, g, b, a), tints the texture if any
     mask                    : vec4<f32>,    // (x, y), (scale_x, scale_y)
     border_radius           : vec4<f32>,    // (top_left, top_right, bottom_right, bottom_left)
     border_radius_mask      : vec4<f32>,    // (top_left, top_right, bottom_right, bottom_left)
-    data0                   : vec4<f32>,    // (depth, rotation, unused, unused)
-    meta0                   : vec4<f32>,    // (type, tex_layer, unused, unused)
+    data0                   : vec4<f32>,    // (depth, rotation, stroke_width / shadow_blur / thickness / backdrop_blur, unused)
+    data1                   : vec4<f32>,    // type specific, shadow shape / gradient / line / arc parameters
+    color1                  : vec4<f32>,    // (r, g, b, a), gradient end color
+    meta0                   : vec4<f32>,    // (type, tex_layer, layer, unused)
+    transform0              : vec4<f32>,    // (m00, m01, m10, m11), column major 2x2 part of the affine transform
+    transform1              : vec4<f32>,    // (translate_x, translate_y, unused, unused)
 };
 
 @group(0) @binding(0) var<uniform> rendererGPUData : RendererGPUData;
 @group(1) @binding(0) var<storage, read> rendererItems : array<RendererItem>;
 
+@group(2) @binding(0) var atlasCoverage : texture_2d_array<f32>;
+@group(2) @binding(1) var atlasColor : texture_2d_array<f32>;
+@group(2) @binding(2) var atlasSampler : sampler;
+
+// what was drawn before the current backdrop item, blurred and premultiplied
+@group(3) @binding(0) var backdropTexture : texture_2d<f32>;
+@group(3) @binding(1) var backdropSampler : sampler;
+
+const ITEM_TYPE_COLOR : u32 = 0u;
+const ITEM_TYPE_TEXTURE_RGBA : u32 = 1u;
+const ITEM_TYPE_TEXTURE_COVERAGE : u32 = 2u;
+const ITEM_TYPE_STROKE : u32 = 3u;
+const ITEM_TYPE_SHADOW : u32 = 4u;
+const ITEM_TYPE_LINEAR_GRADIENT : u32 = 5u;
+const ITEM_TYPE_RADIAL_GRADIENT : u32 = 6u;
+const ITEM_TYPE_ELLIPSE : u32 = 7u;
+const ITEM_TYPE_LINE : u32 = 8u;
+const ITEM_TYPE_ARC : u32 = 9u;
+const ITEM_TYPE_BACKDROP : u32 = 10u;
+
+const TAU : f32 = 6.28318530718;
+
 @vertex
 fn vs_main(
     @builtin(vertex_index) index: u32,
@@ -52,49 +81,89 @@ fn vs_main(
     // offset
     position = position + item.offset_scale.xy;
 
+    // shapes are anti aliased in the fragment shader, so their quad is grown by a pixel to leave room for the edge
+    let frame_res = rendererGPUData.data0.yz;
+    let item_type = u32(item.meta0.x);
+    if (item_type != ITEM_TYPE_TEXTURE_RGBA && item_type != ITEM_TYPE_TEXTURE_COVERAGE) {
+        position = position + (vec2<f32>(tx, ty) * 2.0 - 1.0) / frame_res;
+    }
+    output.position_local = position * 2.0 - 1.0;
+
+    // rotation around the item center and the transform both work in pixels
+    var position_px = position * frame_res;
+    let center = (item.offset_scale.xy + item.offset_scale.zw * 0.5) * frame_res;
+    let rotation = item.data0.y;
+    let rotation_matrix = mat2x2<f32>(cos(rotation), sin(rotation), -sin(rotation), cos(rotation));
+    position_px = center + rotation_matrix * (position_px - center);
+    position_px = mat2x2<f32>(item.transform0.xy, item.transform0.zw) * position_px + item.transform1.xy;
+    position = position_px / frame_res;
+
     let depth = item.data0.x;
     position = position * 2.0 - 1.0;
 
     output.position = vec4<f32>(position, depth, 1.0);
     output.position_raw = vec4<f32>(position, depth, 0.0);
     output.instance_id = instance_id;
+    // texture rows go top to bottom while the quad goes bottom to top
+    output.uv = item.texture_transform.xy + vec2<f32>(tx, 1.0 - ty) * item.texture_transform.zw;
 
 
     return output;
 }
 
-fn point_in_elipse(point: vec2<f32>, center: vec2<f32>, radius: vec2<f32>) -> f32 {
-    let dx = point.x - center.x;
-    let dy = point.y - center.y;
-    let a = radius.x;
-    let b = radius.y;
-    return min(max(dx * dx / (a * a) + dy * dy / (b * b) - 1.0, 0.0) * 100.0, 1.0);
+// signed distance to a rounded box in pixels, rect is (x, y, w, h) with y up,
+// radii are (top_left, top_right, bottom_right, bottom_left)
+fn sd_rounded_box(position: vec2<f32>, rect: vec4<f32>, radii: vec4<f32>) -> f32 {
+    let half_size = rect.zw * 0.5;
+    let p = position - (rect.xy + half_size);
+    var radius = select(radii.w, radii.z, p.x > 0.0);
+    if (p.y > 0.0) {
+        radius = select(radii.x, radii.y, p.x > 0.0);
+    }
+    radius = min(radius, min(half_size.x, half_size.y));
+    let q = abs(p) - half_size + radius;
+    return min(max(q.x, q.y), 0.0) + length(max(q, vec2<f32>(0.0))) - radius;
+}
+
+// approximate, exact for circles
+fn sd_ellipse(position: vec2<f32>, center: vec2<f32>, radii: vec2<f32>) -> f32 {
+    let p = position - center;
+    let k1 = length(p / radii);
+    let k2 = length(p / (radii * radii));
+    return k1 * (k1 - 1.0) / max(k2, 0.0001);
+}
+
+fn sd_segment(position: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let pa = position - a;
+    let ba = b - a;
+    let h = clamp(dot(pa, ba) / max(dot(ba, ba), 0.0001), 0.0, 1.0);
+    return length(pa - ba * h);
 }
 
-fn calculate_border_radius_mask(
-    radius: f32,
-    direction: vec2<f32>,
-    start: vec2<f32>,
-    size: vec2<f32>,
-    position: vec2<f32>,
-) -> f32 {
-    let rad = radius;
-    var rx = rad * min(size.x, size.y) * 0.5;
-    var ry = rad * min(size.x, size.y) * 0.5;
-    let aspect = rendererGPUData.data0.x;    
-    let offset_mask = -direction;
-    if (aspect > 1.0) {
-        rx /= aspect;
-    } else {
-        ry *= aspect;
+// distance to the center line of the arc, sweep is counter clockwise and may be negative
+fn sd_arc(position: vec2<f32>, center: vec2<f32>, radius: f32, start_angle: f32, sweep_angle: f32) -> f32 {
+    let p = position - center;
+    var start = start_angle;
+    var sweep = sweep_angle;
+    if (sweep < 0.0) {
+        start = start + sweep;
+        sweep = -sweep;
     }
-    let center = start + vec2<f32>(rx, ry) * offset_mask;
-    let current_dir = normalize(position - center);
-    let dir = normalize(direction);
-    let cos_angle = dot(current_dir, dir);
-    let cos_factor = min(max(cos_angle - 0.7071, 0.0) * 100.0, 1.0);
-    let ellipse_factor = 1.0 - point_in_elipse(position, center, vec2<f32>(rx, ry));
-    return clamp(ellipse_factor * cos_factor + 1.0 - cos_factor, 0.0, 1.0);
+
+    let angle = atan2(p.y, p.x) - start;
+    let relative = angle - floor(angle / TAU) * TAU;
+    if (relative <= sweep) {
+        return abs(length(p) - radius);
+    }
+
+    let end = start + sweep;
+    let a = radius * vec2<f32>(cos(start), sin(start));
+    let b = radius * vec2<f32>(cos(end), sin(end));
+    return min(length(p - a), length(p - b));
+}
+
+fn coverage(distance: f32, pixel_size: f32) -> f32 {
+    return clamp(0.5 - distance / pixel_size, 0.0, 1.0);
 }
 
 @fragment
@@ -102,47 +171,74 @@ fn fs_main(
     in: VertexOutput
 ) -> FragmentOutput {
     var output: FragmentOutput;
-    // output.color0 = vec4<f32>(1.0, 0.0, 0.0, 1.0);
 
     let item = rendererItems[ in.instance_id ];
-
-    // if position is outside of mask, discard
-    let position = in.position_raw.xy;
-
-    var mask = item.mask;
-    mask = vec4<f32>(
-        mask.xy * 2.0 - 1.0,
-        mask.zw * 2.0
-    );
-
-    
-    var color = item.texture_transform;
-
-    // exclude anything outside the mask box
-    if (position.x < mask.x || position.y < mask.y || position.x > (mask.z + mask.x) || position.y > (mask.w + mask.y)) { 
-        color = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+    let frame_res = rendererGPUData.data0.yz;
+
+    // item shape in pixels, before the transform
+    let position_px = (in.position_local * 0.5 + 0.5) * frame_res;
+    let rect_px = item.offset_scale * vec4<f32>(frame_res, frame_res);
+
+    // derivatives have to be taken in uniform control flow, so before branching on the item type.
+    // pixel_size is the size of a screen pixel in item pixels, so edges stay one pixel wide under scaling
+    let uv_dx = dpdx(in.uv);
+    let uv_dy = dpdy(in.uv);
+    let pixel_size = max(length(fwidth(position_px)) * 0.70710678, 0.0001);
+
+    var color = item.color;
+    let item_type = u32(item.meta0.x);
+    let tex_layer = i32(item.meta0.y);
+    if (item_type == ITEM_TYPE_TEXTURE_RGBA) {
+        color *= textureSampleGrad(atlasColor, atlasSampler, in.uv, tex_layer, uv_dx, uv_dy);
+    } else if (item_type == ITEM_TYPE_TEXTURE_COVERAGE) {
+        color.a *= textureSampleGrad(atlasCoverage, atlasSampler, in.uv, tex_layer, uv_dx, uv_dy).r;
+    } else if (item_type == ITEM_TYPE_STROKE) {
+        let distance = sd_rounded_box(position_px, rect_px, item.border_radius);
+        let stroke_width = item.data0.z;
+        color.a *= coverage(distance, pixel_size) * coverage(-distance - stroke_width, pixel_size);
+    } else if (item_type == ITEM_TYPE_SHADOW) {
+        let distance = sd_rounded_box(position_px, item.data1, item.border_radius);
+        let blur = max(item.data0.z, pixel_size * 0.5);
+        color.a *= 1.0 - smoothstep(-blur, blur, distance);
+    } else if (item_type == ITEM_TYPE_LINEAR_GRADIENT) {
+        let local = (position_px - rect_px.xy) / rect_px.zw;
+        let direction = item.data1.zw - item.data1.xy;
+        let t = clamp(dot(local - item.data1.xy, direction) / max(dot(direction, direction), 0.0001), 0.0, 1.0);
+        color = mix(item.color, item.color1, t);
+    } else if (item_type == ITEM_TYPE_RADIAL_GRADIENT) {
+        let center = rect_px.xy + item.data1.xy * rect_px.zw;
+        let t = clamp(length(position_px - center) / max(item.data1.z, 0.0001), 0.0, 1.0);
+        color = mix(item.color, item.color1, t);
+    } else if (item_type == ITEM_TYPE_ELLIPSE) {
+        let distance = sd_ellipse(position_px, rect_px.xy + rect_px.zw * 0.5, rect_px.zw * 0.5);
+        let stroke_width = item.data0.z;
+        color.a *= coverage(distance, pixel_size);
+        if (stroke_width > 0.0) {
+            color.a *= coverage(-distance - stroke_width, pixel_size);
+        }
+    } else if (item_type == ITEM_TYPE_LINE) {
+        let distance = sd_segment(position_px, item.data1.xy, item.data1.zw) - item.data0.z * 0.5;
+        color.a *= coverage(distance, pixel_size);
+    } else if (item_type == ITEM_TYPE_ARC) {
+        let center = rect_px.xy + rect_px.zw * 0.5;
+        let distance = sd_arc(position_px, center, item.data1.x, item.data1.y, item.data1.z) - item.data0.z * 0.5;
+        color.a *= coverage(distance, pixel_size);
+    } else if (item_type == ITEM_TYPE_BACKDROP) {
+        let backdrop = textureSampleLevel(backdropTexture, backdropSampler, in.position.xy / frame_res, 0.0);
+        let backdrop_color = backdrop.rgb / max(backdrop.a, 0.0001);
+        color = vec4<f32>(mix(backdrop_color, item.color.rgb, item.color.a), max(backdrop.a, item.color.a));
     }
 
-    // exclude anything outside the mask border radius
-    var start = item.offset_scale.xy * 2.0 - 1.0;
-    var size = vec3<f32>(item.offset_scale.zw * 2.0, 0.0);
-    var border_radius = item.border_radius;
-    color.a *= calculate_border_radius_mask(border_radius.x, vec2<f32>(-1.0, 1.0), start + size.zy, size.xy, position);
-    color.a *= calculate_border_radius_mask(border_radius.y, vec2<f32>(1.0, 1.0), start + size.xy, size.xy, position);
-    color.a *= calculate_border_radius_mask(border_radius.z, vec2<f32>(-1.0, -1.0), start + size.zz, size.xy, position);
-    color.a *= calculate_border_radius_mask(border_radius.w, vec2<f32>(1.0, -1.0), start + size.xz, size.xy, position);
-
-    // mask border radius
-    border_radius = item.border_radius_mask;
-    start = mask.xy;
-    size = vec3<f32>(mask.zw, 0.0);
-    color.a *= calculate_border_radius_mask(border_radius.x, vec2<f32>(-1.0, 1.0), start + size.zy, size.xy, position);
-    color.a *= calculate_border_radius_mask(border_radius.y, vec2<f32>(1.0, 1.0), start + size.xy, size.xy, position);
-    color.a *= calculate_border_radius_mask(border_radius.z, vec2<f32>(-1.0, -1.0), start + size.zz, size.xy, position);
-    color.a *= calculate_border_radius_mask(border_radius.w, vec2<f32>(1.0, -1.0), start + size.xz, size.xy, position);
-    
-
+    // fills are cut to the rounded rect, the other shapes already handle it with their own distance
+    let is_rect_fill = item_type <= ITEM_TYPE_TEXTURE_COVERAGE || item_type == ITEM_TYPE_LINEAR_GRADIENT || item_type == ITEM_TYPE_RADIAL_GRADIENT || item_type == ITEM_TYPE_BACKDROP;
+    if (is_rect_fill) {
+        color.a *= coverage(sd_rounded_box(position_px, rect_px, item.border_radius), pixel_size);
+    }
 
+    // the mask is in screen space, after the transform
+    let screen_px = (in.position_raw.xy * 0.5 + 0.5) * frame_res;
+    let mask_px = item.mask * vec4<f32>(frame_res, frame_res);
+    color.a *= coverage(sd_rounded_box(screen_px, mask_px, item.border_radius_mask), 1.0);
 
     output.color0 = color;
 
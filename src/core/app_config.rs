@@ -4,6 +4,11 @@ pub enum AppConfigItem {
     None,
     Monitor(String),
     Trigger(global_hotkey::hotkey::HotKey),
+    MsaaSamples(u32),
+    PresentMode(crate::SwapchainPresentMode),
+    Backend(wgpu::Backends),
+    PowerPreference(wgpu::PowerPreference),
+    FallbackAdapter(bool),
 }
 
 impl Default for AppConfigItem {
@@ -23,6 +28,28 @@ impl crate::ConfigItem for AppConfigItem {
                 let trigger = global_hotkey::hotkey::HotKey::from_str(value.as_str().ok_or("Failed to get trigger as string")?).map_err(|e| format!("Failed to parse trigger: {}", e))?;
                 Ok(Self::Trigger(trigger))
             },
+            "msaa_samples" => {
+                let samples = value.as_u64().ok_or("Failed to get msaa_samples as integer")?;
+                if !matches!(samples, 1 | 2 | 4 | 8 | 16) {
+                    return Err(format!("Invalid msaa_samples: {}, expected 1, 2, 4, 8 or 16", samples));
+                }
+                Ok(Self::MsaaSamples(samples as u32))
+            },
+            "present_mode" => {
+                let present_mode = value.as_str().ok_or("Failed to get present_mode as string")?;
+                Ok(Self::PresentMode(crate::SwapchainPresentMode::from_name(present_mode)?))
+            },
+            "backend" => {
+                let backend = value.as_str().ok_or("Failed to get backend as string")?;
+                Ok(Self::Backend(crate::GPUInstanceOptions::parse_backends(backend)?))
+            },
+            "power_preference" => {
+                let power_preference = value.as_str().ok_or("Failed to get power_preference as string")?;
+                Ok(Self::PowerPreference(crate::GPUInstanceOptions::parse_power_preference(power_preference)?))
+            },
+            "fallback_adapter" => {
+                Ok(Self::FallbackAdapter(value.as_bool().ok_or("Failed to get fallback_adapter as bool")?))
+            },
             _ => Err(format!("Unknown key: {}", key)),
         }
     }
@@ -31,6 +58,11 @@ impl crate::ConfigItem for AppConfigItem {
         match self {
             Self::Monitor(monitor) => ("monitor", serde_yaml::Value::String(monitor.to_string())),
             Self::Trigger(_trigger) => ("trigger", serde_yaml::Value::String("cmd+alt+space".to_string())), // currently we cannot save hotkey
+            Self::MsaaSamples(samples) => ("msaa_samples", serde_yaml::Value::Number((*samples).into())),
+            Self::PresentMode(present_mode) => ("present_mode", serde_yaml::Value::String(present_mode.name().to_string())),
+            Self::Backend(backends) => ("backend", serde_yaml::Value::String(crate::GPUInstanceOptions::backends_name(*backends))),
+            Self::PowerPreference(power_preference) => ("power_preference", serde_yaml::Value::String(crate::GPUInstanceOptions::power_preference_name(*power_preference).to_string())),
+            Self::FallbackAdapter(fallback_adapter) => ("fallback_adapter", serde_yaml::Value::Bool(*fallback_adapter)),
             Self::None => panic!("Cannot save None"),
         }
     }
@@ -0,0 +1,57 @@
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum CommandLineCommand {
+    #[default]
+    Run,
+    GpuInfo,    // lists the adapters and surface capabilities, then exits
+}
+
+// the gpu options override the ones in the config when given
+pub struct CommandLine {
+    pub command             : CommandLineCommand,
+    pub debug_atlas         : bool,
+    pub present_mode        : Option<crate::SwapchainPresentMode>,
+    pub backend             : Option<wgpu::Backends>,
+    pub power_preference    : Option<wgpu::PowerPreference>,
+    pub fallback_adapter    : bool,
+}
+
+impl Default for CommandLine {
+    fn default() -> Self {
+        Self {
+            command             : CommandLineCommand::Run,
+            debug_atlas         : false,
+            present_mode        : None,
+            backend             : None,
+            power_preference    : None,
+            fallback_adapter    : false,
+        }
+    }
+}
+
+impl CommandLine {
+
+    // the first argument is expected to be the executable path
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut obj = Self::default();
+        let mut args = args.skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "gpu-info" => obj.command = CommandLineCommand::GpuInfo,
+                "--debug-atlas" => obj.debug_atlas = true,
+                "--present-mode" => obj.present_mode = Some(crate::SwapchainPresentMode::from_name(&Self::value(&mut args, &arg)?)?),
+                "--backend" => obj.backend = Some(crate::GPUInstanceOptions::parse_backends(&Self::value(&mut args, &arg)?)?),
+                "--power-preference" => obj.power_preference = Some(crate::GPUInstanceOptions::parse_power_preference(&Self::value(&mut args, &arg)?)?),
+                "--fallback-adapter" => obj.fallback_adapter = true,
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+
+        Ok(obj)
+    }
+
+    fn value(args: &mut impl Iterator<Item = String>, arg: &str) -> Result<String, String> {
+        args.next().ok_or(format!("Missing value for {}", arg))
+    }
+
+}
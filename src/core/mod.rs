@@ -7,4 +7,7 @@ pub use config_store::*;
 mod app_config;
 pub use app_config::*;
 
+mod command_line;
+pub use command_line::*;
+
 pub mod utils;
\ No newline at end of file
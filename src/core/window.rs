@@ -1,13 +1,14 @@
 pub struct Window {
     winit_window: winit::window::Window,
+    visible     : std::cell::Cell<bool>,   // winit can not tell on every platform (wayland), so it is tracked here
 }
 
 impl Window {
-    pub async fn new(event_loop: &winit::event_loop::EventLoop<()>) -> Result<Self, String> {
+    pub async fn new<T: 'static>(event_loop: &winit::event_loop::EventLoop<T>, visible: bool) -> Result<Self, String> {
         let winit_window = winit::window::WindowBuilder::new()
             .with_title("Global Hotkey")
             // .with_content_protected(true)
-            // .with_visible(false)
+            .with_visible(visible)
             .with_resizable(false)
             //.with_window_level(winit::window::WindowLevel::AlwaysOnTop)
             .with_decorations(false)
@@ -17,6 +18,7 @@ impl Window {
         
         Ok(Self {
             winit_window        : winit_window,
+            visible             : std::cell::Cell::new(visible),
         })
     }
 
@@ -48,6 +50,11 @@ impl Window {
         }
     }
 
+    // refresh rate of the monitor the window is on, None when the platform does not report it
+    pub fn refresh_rate_millihertz(&self) -> Option<u32> {
+        self.winit_window.current_monitor().and_then(|monitor| monitor.refresh_rate_millihertz())
+    }
+
     pub fn scale_factor(&self) -> f64 {
         self.winit_window.scale_factor()
     }
@@ -105,16 +112,19 @@ impl Window {
         self.winit_window.is_minimized().unwrap()
     }
 
+    // whether the window was last shown or hidden, it can still be covered or minimized
     pub fn is_visible(&self) -> bool {
-        self.winit_window.is_visible().unwrap()
+        self.visible.get()
     }
 
     pub fn hide(&self) {
         self.winit_window.set_visible(false);
+        self.visible.set(false);
     }
 
     pub fn show(&self) {
         self.winit_window.set_visible(true);
+        self.visible.set(true);
     }
 
     pub fn winit_handle(&self) -> &winit::window::Window {
@@ -0,0 +1,141 @@
+// draws the texture packer layers with their regions and free spaces, for debugging the atlas
+pub struct AtlasDebugView {
+    visible         : bool,
+    cursor          : (f32, f32),   // in logical pixels, origin at the bottom left like the renderer
+}
+
+impl AtlasDebugView {
+
+    const MARGIN: f32 = 20.0;
+    const LABEL_SIZE: f32 = 16.0;
+
+    pub fn new(visible: bool) -> Self {
+        Self {
+            visible         : visible,
+            cursor          : (-1.0, -1.0),
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn set_cursor(&mut self, x: f32, y: f32) {
+        self.cursor = (x, y);
+    }
+
+    // font glyphs are expected in the packer as "<glyph_prefix><char>", used for the labels.
+    // frame_res is the logical size of the frame, see Renderer::logical_size
+    pub fn render(&self, renderer: &mut crate::Renderer, packer: &crate::TexturePacker, font: &crate::Font, glyph_prefix: &str, frame_res: (f32, f32)) {
+        if !self.visible {
+            return;
+        }
+
+        let glyphs = crate::RendererGlyphs { font: font, packer: packer, prefix: glyph_prefix };
+        let (width, height, depth) = packer.size();
+        let cell = ((frame_res.0 - Self::MARGIN * (depth + 1) as f32) / depth as f32)
+            .min(frame_res.1 - Self::MARGIN * 3.0 - Self::LABEL_SIZE);
+        if cell <= 0.0 {
+            return;
+        }
+
+        // drawn above the app whenever it is called
+        let previous_layer = renderer.layer();
+        renderer.set_layer(crate::RendererLayer::Overlay);
+
+        let rect = |renderer: &mut crate::Renderer, x: f32, y: f32, w: f32, h: f32| {
+            renderer.rectp(x, y, w, h);
+        };
+
+        // atlas rows go top to bottom, the renderer goes bottom to top
+        let to_screen = |origin: (f32, f32), region: &crate::TexturePackerRegion| -> (f32, f32, f32, f32) {
+            (
+                origin.0 + region.x as f32 / width as f32 * cell,
+                origin.1 + (1.0 - (region.y + region.height) as f32 / height as f32) * cell,
+                region.width as f32 / width as f32 * cell,
+                region.height as f32 / height as f32 * cell,
+            )
+        };
+
+        renderer.set_texture(None);
+        renderer.set_color(0.05, 0.05, 0.05, 0.9);
+        rect(renderer, 0.0, 0.0, frame_res.0, frame_res.1);
+
+        let regions = packer.regions().collect::<Vec<_>>();
+        let free = packer.free_regions().collect::<Vec<_>>();
+        let mut hovered = None;
+
+        for layer in 0..depth {
+            let origin = (Self::MARGIN + layer as f32 * (cell + Self::MARGIN), Self::MARGIN);
+
+            renderer.set_color(0.15, 0.15, 0.15, 1.0);
+            rect(renderer, origin.0, origin.1, cell, cell);
+
+            // free space
+            let mut free_area = 0u64;
+            renderer.set_color(0.1, 0.6, 0.2, 0.35);
+            for space in free.iter().filter(|space| space.layer == layer) {
+                let (x, y, w, h) = to_screen(origin, space);
+                rect(renderer, x, y, w, h);
+                free_area += space.width as u64 * space.height as u64;
+            }
+
+            // layer contents
+            renderer.set_color(1.0, 1.0, 1.0, 1.0);
+            renderer.set_texture(Some(&crate::TexturePackerRegion {
+                x           : 0,
+                y           : 0,
+                width       : width,
+                height      : height,
+                layer       : layer,
+                uv          : (0.0, 0.0, 1.0, 1.0),
+                format      : packer.format(),
+            }));
+            rect(renderer, origin.0, origin.1, cell, cell);
+            renderer.set_texture(None);
+
+            // region outlines
+            let mut region_count = 0;
+            renderer.set_color(0.9, 0.6, 0.1, 0.6);
+            for (name, region) in regions.iter().filter(|(_, region)| region.layer == layer) {
+                let (x, y, w, h) = to_screen(origin, region);
+                rect(renderer, x, y, w, 1.0);
+                rect(renderer, x, y + h - 1.0, w, 1.0);
+                rect(renderer, x, y, 1.0, h);
+                rect(renderer, x + w - 1.0, y, 1.0, h);
+                region_count += 1;
+
+                if self.cursor.0 >= x && self.cursor.0 < x + w && self.cursor.1 >= y && self.cursor.1 < y + h {
+                    hovered = Some((name.as_str(), *region, (x, y, w, h)));
+                }
+            }
+
+            let free_percent = free_area as f64 / (width as u64 * height as u64) as f64 * 100.0;
+            renderer.set_color(1.0, 1.0, 1.0, 1.0);
+            renderer.textp(glyphs, origin.0, origin.1 + cell + Self::MARGIN, Self::LABEL_SIZE,
+                &format!("layer {}: {} regions, {:.1}% free", layer, region_count, free_percent));
+        }
+
+        if let Some((name, region, (x, y, w, h))) = hovered {
+            renderer.set_color(1.0, 1.0, 1.0, 0.3);
+            rect(renderer, x, y, w, h);
+
+            renderer.set_layer(crate::RendererLayer::Tooltip);
+
+            let label = format!("{} {}x{} at {},{}", name.escape_debug(), region.width, region.height, region.x, region.y);
+            let label_x = self.cursor.0 + 12.0;
+            let label_y = self.cursor.1 + 12.0;
+            renderer.set_color(0.0, 0.0, 0.0, 0.85);
+            rect(renderer, label_x - 4.0, label_y - 6.0, label.chars().count() as f32 * Self::LABEL_SIZE * 0.6 + 8.0, Self::LABEL_SIZE + 8.0);
+            renderer.set_color(1.0, 1.0, 1.0, 1.0);
+            renderer.textp(glyphs, label_x, label_y, Self::LABEL_SIZE, &label);
+        }
+
+        renderer.set_layer(previous_layer);
+    }
+
+}
@@ -0,0 +1,269 @@
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]  #[repr(C)]
+struct BlurPassGPUData {
+    data0           : [f32; 4], // (step_x, step_y) in uv, radius in pixels, unused
+}
+
+impl Default for BlurPassGPUData {
+    fn default() -> Self {
+        Self {
+            data0       : [0.0, 0.0, 0.0, 0.0],
+        }
+    }
+}
+
+// intermediate and output textures, recreated when the source size changes
+struct BlurPassTargets {
+    size                : (u32, u32),
+    horizontal          : wgpu::TextureView,
+    horizontal_bind_group: wgpu::BindGroup,
+    output              : wgpu::TextureView,
+}
+
+// separable gaussian blur of regions of a texture, horizontal into an intermediate texture then vertical into the
+// output. also blits textures, both with the format given to new
+pub struct BlurPass {
+    blur_pipeline   : crate::GraphicsPipeline,
+    blit_pipeline   : crate::GraphicsPipeline,
+    gpu_data        : crate::UniformRingBuffer<BlurPassGPUData>,
+    texture_layout  : wgpu::BindGroupLayout,
+    sampler         : wgpu::Sampler,
+    format          : wgpu::TextureFormat,
+    targets         : Option<BlurPassTargets>,
+    generation      : u64,
+}
+
+impl BlurPass {
+
+    // fs_blur takes at most MAX_TAPS samples per side, a larger radius would cut the kernel off
+    pub const MAX_RADIUS: f32 = 64.0;
+
+    pub async fn new(instance: &crate::GPUInstance, format: wgpu::TextureFormat) -> Result<Self, String> {
+        // one slot per pass of a blur, they are all in the same submission
+        let gpu_data = crate::UniformRingBuffer::<BlurPassGPUData>::new(instance, 2, Some("BlurPassGPUData")).await?;
+
+        let sampler = instance.device().create_sampler(&wgpu::SamplerDescriptor {
+            label               : Some("BlurPassSampler"),
+            address_mode_u      : wgpu::AddressMode::ClampToEdge,
+            address_mode_v      : wgpu::AddressMode::ClampToEdge,
+            address_mode_w      : wgpu::AddressMode::ClampToEdge,
+            mag_filter          : wgpu::FilterMode::Linear,
+            min_filter          : wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let texture_layout = instance.device().create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label       : Some("BlurPassSource"),
+            entries     : &[
+                wgpu::BindGroupLayoutEntry {
+                    binding     : 0,
+                    visibility  : wgpu::ShaderStages::FRAGMENT,
+                    ty          : wgpu::BindingType::Texture {
+                                    sample_type     : wgpu::TextureSampleType::Float { filterable: true },
+                                    view_dimension  : wgpu::TextureViewDimension::D2,
+                                    multisampled    : false,
+                                },
+                    count       : None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding     : 1,
+                    visibility  : wgpu::ShaderStages::FRAGMENT,
+                    ty          : wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count       : None,
+                },
+            ],
+        });
+
+        let shader_source = include_str!("../assets/shaders/blur.wgsl").to_string();
+        let shader_path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/assets/shaders/blur.wgsl");
+
+        let blur_pipeline = crate::GraphicsPipeline::builder(shader_source.clone(), format)
+            .shader_path(shader_path)
+            .fragment_entry(Some("fs_blur"))
+            .blend_mode(crate::GraphicsPipelineBlendMode::Replace)
+            .bind_group_layouts(vec![gpu_data.layout(), &texture_layout])
+            .label("BlurPassBlur")
+            .build(instance).await?;
+
+        // sources are premultiplied, see Renderer::flush
+        let blit_pipeline = crate::GraphicsPipeline::builder(shader_source, format)
+            .shader_path(shader_path)
+            .fragment_entry(Some("fs_blit"))
+            .blend_mode(crate::GraphicsPipelineBlendMode::Premultiplied)
+            .bind_group_layouts(vec![gpu_data.layout(), &texture_layout])
+            .label("BlurPassBlit")
+            .build(instance).await?;
+
+        Ok(Self {
+            blur_pipeline   : blur_pipeline,
+            blit_pipeline   : blit_pipeline,
+            gpu_data        : gpu_data,
+            texture_layout  : texture_layout,
+            sampler         : sampler,
+            format          : format,
+            targets         : None,
+            generation      : 0,
+        })
+    }
+
+    // how far outside a region the blur reads, in pixels. fs_blur takes one tap per pixel of the radius
+    pub fn reach(radius: f32) -> u32 {
+        radius.clamp(0.0, Self::MAX_RADIUS).ceil() as u32 + 1
+    }
+
+    // the source has to be a single sampled texture of the given size, radius is in pixels and clamped to MAX_RADIUS.
+    // only the regions (x, y, width, height in pixels, y down) of the output are valid afterwards, the rest is cleared
+    pub fn blur(&mut self, instance: &crate::GPUInstance, source: &wgpu::TextureView, size: (u32, u32), radius: f32, regions: &[(u32, u32, u32, u32)]) -> Result<(), String> {
+        // the vertical pass reads the horizontal one up to the reach away, so both run on the grown regions
+        let reach = Self::reach(radius);
+        let scissors = regions.iter().filter_map(|&(x, y, width, height)| {
+            let (x0, y0) = (x.saturating_sub(reach).min(size.0), y.saturating_sub(reach).min(size.1));
+            let x1 = x.saturating_add(width).saturating_add(reach).min(size.0);
+            let y1 = y.saturating_add(height).saturating_add(reach).min(size.1);
+            (x1 > x0 && y1 > y0).then_some((x0, y0, x1 - x0, y1 - y0))
+        }).collect::<Vec<_>>();
+
+        self.update_targets(instance, size);
+        let source_bind_group = self.create_source_bind_group(instance, source);
+        let radius = radius.clamp(0.0, Self::MAX_RADIUS);
+
+        // the previous blur has been submitted, so its slots can be written again
+        self.gpu_data.reset();
+        let horizontal_offset = self.gpu_data.push(instance, BlurPassGPUData { data0: [1.0 / size.0 as f32, 0.0, radius, 0.0] })?;
+        let vertical_offset = self.gpu_data.push(instance, BlurPassGPUData { data0: [0.0, 1.0 / size.1 as f32, radius, 0.0] })?;
+
+        let targets = self.targets.as_ref().unwrap();
+        let mut encoder = instance.encoder("BlurPass::blur.encoder");
+        Self::encode(&mut encoder, &self.blur_pipeline, (self.gpu_data.bind_group(), horizontal_offset), &source_bind_group, (&targets.horizontal, wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT)), Some(&scissors), "BlurPass::blur.horizontal");
+        Self::encode(&mut encoder, &self.blur_pipeline, (self.gpu_data.bind_group(), vertical_offset), &targets.horizontal_bind_group, (&targets.output, wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT)), Some(&scissors), "BlurPass::blur.vertical");
+        instance.submit(encoder);
+        Ok(())
+    }
+
+    // blends the premultiplied source over the target, clearing it first if asked to
+    pub fn blit(&mut self, instance: &crate::GPUInstance, source: &wgpu::TextureView, target: &wgpu::TextureView, clear: bool) -> Result<(), String> {
+        let source_bind_group = self.create_source_bind_group(instance, source);
+        let load = if clear {
+            wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT)
+        } else {
+            wgpu::LoadOp::Load
+        };
+
+        // fs_blit does not read the uniform, but the layout still needs it bound
+        self.gpu_data.reset();
+        let offset = self.gpu_data.push(instance, BlurPassGPUData::default())?;
+
+        let mut encoder = instance.encoder("BlurPass::blit.encoder");
+        Self::encode(&mut encoder, &self.blit_pipeline, (self.gpu_data.bind_group(), offset), &source_bind_group, (target, load), None, "BlurPass::blit");
+        instance.submit(encoder);
+        Ok(())
+    }
+
+    // the result of the last blur
+    pub fn output_view(&self) -> Option<&wgpu::TextureView> {
+        self.targets.as_ref().map(|targets| &targets.output)
+    }
+
+    // changes whenever the output texture is recreated, so bind groups using it can be rebuilt
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    // returns whether any pipeline was replaced
+    pub fn reload_shaders(&mut self, instance: &crate::GPUInstance) -> bool {
+        let blur_reloaded = pollster::block_on(self.blur_pipeline.reload_if_changed(instance));
+        let blit_reloaded = pollster::block_on(self.blit_pipeline.reload_if_changed(instance));
+        blur_reloaded || blit_reloaded
+    }
+
+    fn update_targets(&mut self, instance: &crate::GPUInstance, size: (u32, u32)) {
+        if self.targets.as_ref().map(|targets| targets.size == size).unwrap_or(false) {
+            return;
+        }
+
+        let horizontal = Self::create_texture(instance, self.format, size, "BlurPassHorizontal");
+        let horizontal_bind_group = self.create_source_bind_group(instance, &horizontal);
+        let output = Self::create_texture(instance, self.format, size, "BlurPassOutput");
+
+        self.targets = Some(BlurPassTargets {
+            size                : size,
+            horizontal          : horizontal,
+            horizontal_bind_group: horizontal_bind_group,
+            output              : output,
+        });
+        self.generation += 1;
+    }
+
+    fn create_texture(instance: &crate::GPUInstance, format: wgpu::TextureFormat, size: (u32, u32), label: &str) -> wgpu::TextureView {
+        let texture = instance.device().create_texture(&wgpu::TextureDescriptor {
+            label               : Some(label),
+            size                : wgpu::Extent3d {
+                                    width                   : size.0.max(1),
+                                    height                  : size.1.max(1),
+                                    depth_or_array_layers   : 1,
+                                },
+            mip_level_count     : 1,
+            sample_count        : 1,
+            dimension           : wgpu::TextureDimension::D2,
+            format              : format,
+            usage               : wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats        : &[],
+        });
+        texture.create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    fn create_source_bind_group(&self, instance: &crate::GPUInstance, source: &wgpu::TextureView) -> wgpu::BindGroup {
+        instance.device().create_bind_group(&wgpu::BindGroupDescriptor {
+            label       : Some("BlurPassSource"),
+            layout      : &self.texture_layout,
+            entries     : &[
+                wgpu::BindGroupEntry {
+                    binding     : 0,
+                    resource    : wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding     : 1,
+                    resource    : wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        })
+    }
+
+    // draws over the whole target, or once per scissor rect (x, y, width, height) when given
+    fn encode(encoder: &mut wgpu::CommandEncoder, pipeline: &crate::GraphicsPipeline, gpu_data: (&wgpu::BindGroup, u32), source: &wgpu::BindGroup, target: (&wgpu::TextureView, wgpu::LoadOp<wgpu::Color>), scissors: Option<&[(u32, u32, u32, u32)]>, label: &str) {
+        let (target, load) = target;
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[
+                Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: load,
+                        store:  wgpu::StoreOp::Store,
+                    }
+                })
+            ],
+            depth_stencil_attachment: None,
+            label: Some(label),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        rpass.set_pipeline(pipeline.pipeline());
+        rpass.set_bind_group(0, gpu_data.0, &[gpu_data.1]);
+        rpass.set_bind_group(1, source, &[]);
+        match scissors {
+            Some(scissors) => {
+                for &(x, y, width, height) in scissors {
+                    rpass.set_scissor_rect(x, y, width, height);
+                    rpass.draw(0..3, 0..1);
+                }
+            },
+            None => rpass.draw(0..3, 0..1),
+        }
+    }
+
+}
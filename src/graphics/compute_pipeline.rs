@@ -0,0 +1,70 @@
+pub struct ComputePipeline {
+    layout          : wgpu::PipelineLayout,
+    pipeline        : wgpu::ComputePipeline,
+    workgroup_size  : (u32, u32, u32),
+}
+
+impl ComputePipeline {
+
+    // the shader is validated with naga first, the workgroup size is taken from the entry point
+    pub async fn new(instance: &crate::GPUInstance, shader_source: String, entry_point: &str, bind_grp_layouts: Vec<&wgpu::BindGroupLayout>, label: Option<&str>) -> Result<Self, String> {
+        let module = crate::GraphicsPipeline::validate_wgsl(&shader_source)?;
+        let workgroup_size = module.entry_points.iter()
+            .find(|entry| entry.name == entry_point && entry.stage == naga::ShaderStage::Compute)
+            .map(|entry| (entry.workgroup_size[0], entry.workgroup_size[1], entry.workgroup_size[2]))
+            .ok_or(format!("No compute entry point named {}", entry_point))?;
+
+        let device = instance.device();
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label       : label,
+            source      : wgpu::ShaderSource::Wgsl(shader_source.into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label                   : label,
+            bind_group_layouts      : &bind_grp_layouts,
+            push_constant_ranges    : &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label           : label,
+            layout          : Some(&layout),
+            module          : &shader,
+            entry_point     : entry_point,
+        });
+
+        if let Some(e) = device.pop_error_scope().await {
+            return Err(format!("Failed to create compute pipeline {}: {}", label.unwrap_or(""), e));
+        }
+
+        Ok(Self {
+            layout          : layout,
+            pipeline        : pipeline,
+            workgroup_size  : workgroup_size,
+        })
+    }
+
+    pub fn layout(&self) -> &wgpu::PipelineLayout {
+        &self.layout
+    }
+
+    pub fn pipeline(&self) -> &wgpu::ComputePipeline {
+        &self.pipeline
+    }
+
+    pub fn workgroup_size(&self) -> (u32, u32, u32) {
+        self.workgroup_size
+    }
+
+    // workgroups needed to cover size invocations, the shader has to skip the ones past the end
+    pub fn workgroup_count(&self, size: (u32, u32, u32)) -> (u32, u32, u32) {
+        (
+            size.0.div_ceil(self.workgroup_size.0),
+            size.1.div_ceil(self.workgroup_size.1),
+            size.2.div_ceil(self.workgroup_size.2),
+        )
+    }
+
+}
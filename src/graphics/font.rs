@@ -12,6 +12,7 @@ pub struct FontGlyph {
 pub struct Font
 {
     glyph_count             : u32,
+    scale                   : f32,
     glyphs                  : std::collections::HashMap<char, FontGlyph>,
     
 }
@@ -20,6 +21,15 @@ impl Font
 {
     
     pub async fn new(bytes: &[u8], scale: f32) -> Result<Self, String> {
+        Self::load(bytes, scale, true)
+    }
+
+    // same glyph metrics as new, but with empty bitmaps, for when the glyphs are already packed
+    pub async fn new_metrics_only(bytes: &[u8], scale: f32) -> Result<Self, String> {
+        Self::load(bytes, scale, false)
+    }
+
+    fn load(bytes: &[u8], scale: f32, rasterize: bool) -> Result<Self, String> {
 
         let font = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default()).map_err(|e| format!("Failed to create font: {}", e))?;
 
@@ -31,7 +41,11 @@ impl Font
 
         let keys = font.chars().keys().cloned().collect::<Vec<char>>();
         keys.into_iter().for_each(|id| {
-            let (glyph, bitmap) = font.rasterize(id, scale);
+            let (glyph, bitmap) = if rasterize {
+                font.rasterize(id, scale)
+            } else {
+                (font.metrics(id, scale), Vec::new())
+            };
             
             glyphs.insert(id as char, FontGlyph {
                 id              : id as char,
@@ -61,6 +75,7 @@ impl Font
         
         Ok(Self {
             glyph_count     : glyphs.len() as u32,
+            scale           : scale,
             glyphs          : glyphs,
         })
     }
@@ -82,5 +97,9 @@ impl Font
         &self.glyphs
     }
 
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
     
 }
\ No newline at end of file
@@ -1,52 +1,117 @@
+// how the adapter and the swapchain are picked, set from the config and the command line
+#[derive(Clone, Debug)]
+pub struct GPUInstanceOptions {
+    pub backends                : wgpu::Backends,
+    pub power_preference        : wgpu::PowerPreference,
+    pub force_fallback_adapter  : bool,
+    pub present_mode            : crate::SwapchainPresentMode,
+}
+
+impl Default for GPUInstanceOptions {
+    fn default() -> Self {
+        Self {
+            backends                : wgpu::Backends::PRIMARY,
+            power_preference        : wgpu::PowerPreference::HighPerformance,
+            force_fallback_adapter  : false,
+            present_mode            : crate::SwapchainPresentMode::Auto,
+        }
+    }
+}
+
+impl GPUInstanceOptions {
+
+    const BACKEND_NAMES: [(wgpu::Backends, &'static str); 6] = [
+        (wgpu::Backends::VULKAN,            "vulkan"),
+        (wgpu::Backends::GL,                "gl"),
+        (wgpu::Backends::METAL,             "metal"),
+        (wgpu::Backends::DX12,              "dx12"),
+        (wgpu::Backends::DX11,              "dx11"),
+        (wgpu::Backends::BROWSER_WEBGPU,    "webgpu"),
+    ];
+
+    // a single name or a comma separated list of them, like "vulkan,gl"
+    pub fn parse_backends(names: &str) -> Result<wgpu::Backends, String> {
+        let mut backends = wgpu::Backends::empty();
+        for name in names.split(',').map(|name| name.trim()) {
+            backends |= match name {
+                "primary"   => wgpu::Backends::PRIMARY,
+                "all"       => wgpu::Backends::all(),
+                _ => Self::BACKEND_NAMES.iter().find(|(_, backend_name)| *backend_name == name).map(|(backend, _)| *backend)
+                    .ok_or(format!("Unknown backend: {}, expected primary, all, vulkan, gl, metal, dx12, dx11 or webgpu, or a comma separated list of them", name))?,
+            };
+        }
+        Ok(backends)
+    }
+
+    // names every backend in the set, parse_backends reads it back
+    pub fn backends_name(backends: wgpu::Backends) -> String {
+        if backends == wgpu::Backends::all() {
+            return "all".to_string();
+        }
+        if backends == wgpu::Backends::PRIMARY {
+            return "primary".to_string();
+        }
+
+        let names = Self::BACKEND_NAMES.iter().filter(|(backend, _)| backends.contains(*backend)).map(|(_, name)| *name).collect::<Vec<_>>();
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.join(",")
+        }
+    }
+
+    pub fn parse_power_preference(name: &str) -> Result<wgpu::PowerPreference, String> {
+        match name {
+            "high_performance"  => Ok(wgpu::PowerPreference::HighPerformance),
+            "low_power"         => Ok(wgpu::PowerPreference::LowPower),
+            "none"              => Ok(wgpu::PowerPreference::None),
+            _ => Err(format!("Unknown power preference: {}, expected high_performance, low_power or none", name)),
+        }
+    }
+
+    pub fn power_preference_name(power_preference: wgpu::PowerPreference) -> &'static str {
+        match power_preference {
+            wgpu::PowerPreference::HighPerformance  => "high_performance",
+            wgpu::PowerPreference::LowPower         => "low_power",
+            wgpu::PowerPreference::None             => "none",
+        }
+    }
+
+}
+
 pub struct GPUInstance {
     instance        : wgpu::Instance,
     adapter         : wgpu::Adapter,
-    device          : wgpu::Device,
+    device          : std::sync::Arc<wgpu::Device>,    // shared with the thread polling for map_read
     queue           : wgpu::Queue,
-    surface         : wgpu::Surface,
+    surface         : Option<wgpu::Surface>,
     swapchain       : Option<crate::Swapchain>,
+    options         : GPUInstanceOptions,
 }
 
 
 impl GPUInstance {
-    pub async fn new(window: &crate::Window) -> Result<Self, String> {
-        let instance = wgpu::Instance::new( wgpu::InstanceDescriptor {
-            backends        : wgpu::Backends::PRIMARY,
-            flags           : wgpu::InstanceFlags::empty(),
-            ..Default::default()
-        });
+    pub async fn new(window: &crate::Window, options: GPUInstanceOptions) -> Result<Self, String> {
+        let instance = Self::create_instance(options.backends);
 
         let surface = unsafe { instance.create_surface(window.winit_handle()) }.map_err(|e| format!("Failed to create surface: {}", e))?;
 
-        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference            : wgpu::PowerPreference::HighPerformance,
-            compatible_surface          : Some(&surface),
-            force_fallback_adapter      : false,
-        }).await.ok_or("Failed to find an appropriate adapter")?;
-
-        let limits = wgpu::Limits::default();
-        let features = wgpu::Features::empty();
-
+        let adapter = Self::request_adapter(&instance, &surface, &options).await?;
+        log::info!("Adapter: {:?}", adapter.get_info());
 
-        let (device, queue) = adapter.request_device(
-            &wgpu::DeviceDescriptor {
-                features   : features,
-                limits     : limits,
-                label      : Some("GPUInstance"),
-            },
-            None,
-        ).await.map_err(|e| format!("Failed to create device: {}", e))?;
+        let (device, queue) = Self::create_device(&adapter).await?;
 
         let mut obj = Self {
             instance            : instance,
             adapter             : adapter,
-            device              : device,
+            device              : std::sync::Arc::new(device),
             queue               : queue,
-            surface             : surface,
+            surface             : Some(surface),
             swapchain           : None,
+            options             : options,
         };
 
-        let mut swapchain = crate::Swapchain::new(&obj).await?;
+        let mut swapchain = crate::Swapchain::new(&obj, obj.options.present_mode).await?;
         swapchain.resize(window.width(), window.height());
         
         obj.swapchain = Some(swapchain);
@@ -55,8 +120,126 @@ impl GPUInstance {
         Ok(obj)
     }
 
-    pub fn surface(&self) -> &wgpu::Surface {
-        &self.surface
+    // no surface (and so no swapchain) is created, the adapter is picked from the options like new does and the
+    // present mode is ignored
+    pub async fn new_headless(options: GPUInstanceOptions) -> Result<Self, String> {
+        let instance = Self::create_instance(options.backends);
+
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference            : options.power_preference,
+            compatible_surface          : None,
+            force_fallback_adapter      : options.force_fallback_adapter,
+        }).await.ok_or(format!("Failed to find an appropriate adapter (backend {}, fallback adapter {})",
+            GPUInstanceOptions::backends_name(options.backends), options.force_fallback_adapter))?;
+        log::info!("Headless adapter: {:?}", adapter.get_info());
+
+        let (device, queue) = Self::create_device(&adapter).await?;
+
+        Ok(Self {
+            instance            : instance,
+            adapter             : adapter,
+            device              : std::sync::Arc::new(device),
+            queue               : queue,
+            surface             : None,
+            swapchain           : None,
+            options             : options,
+        })
+    }
+
+    // lists the adapters of every backend with their capabilities for the window surface, then the one new would
+    // pick with the given options
+    pub async fn describe_adapters(window: &crate::Window, options: &GPUInstanceOptions) -> Result<String, String> {
+        let mut out = String::new();
+
+        {
+            let instance = Self::create_instance(wgpu::Backends::all());
+            let surface = unsafe { instance.create_surface(window.winit_handle()) }.map_err(|e| format!("Failed to create surface: {}", e))?;
+
+            for (index, adapter) in instance.enumerate_adapters(wgpu::Backends::all()).enumerate() {
+                let info = adapter.get_info();
+                out.push_str(&format!("[{}] {} ({:?}, {:?})\n", index, info.name, info.backend, info.device_type));
+                out.push_str(&format!("    driver          : {} {}\n", info.driver, info.driver_info));
+                out.push_str(&format!("    vendor/device   : {:#06x}/{:#06x}\n", info.vendor, info.device));
+
+                if !adapter.is_surface_supported(&surface) {
+                    out.push_str("    surface         : not supported\n");
+                    continue;
+                }
+                let caps = surface.get_capabilities(&adapter);
+                out.push_str(&format!("    formats         : {:?}\n", caps.formats));
+                out.push_str(&format!("    present modes   : {:?}\n", caps.present_modes));
+                out.push_str(&format!("    alpha modes     : {:?}\n", caps.alpha_modes));
+            }
+        }
+
+        // a separate instance limited to the configured backends, like new uses
+        let instance = Self::create_instance(options.backends);
+        let surface = unsafe { instance.create_surface(window.winit_handle()) }.map_err(|e| format!("Failed to create surface: {}", e))?;
+        out.push_str(&format!("\nbackend {}, power preference {}, fallback adapter {}, present mode {}: ",
+            GPUInstanceOptions::backends_name(options.backends),
+            GPUInstanceOptions::power_preference_name(options.power_preference),
+            options.force_fallback_adapter,
+            options.present_mode.name()));
+        match Self::request_adapter(&instance, &surface, options).await {
+            Ok(adapter) => {
+                let info = adapter.get_info();
+                out.push_str(&format!("{} ({:?})\n", info.name, info.backend));
+            },
+            Err(e) => out.push_str(&format!("{}\n", e)),
+        }
+
+        Ok(out)
+    }
+
+    fn create_instance(backends: wgpu::Backends) -> wgpu::Instance {
+        wgpu::Instance::new( wgpu::InstanceDescriptor {
+            backends        : backends,
+            flags           : wgpu::InstanceFlags::empty(),
+            ..Default::default()
+        })
+    }
+
+    async fn request_adapter(instance: &wgpu::Instance, surface: &wgpu::Surface, options: &GPUInstanceOptions) -> Result<wgpu::Adapter, String> {
+        instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference            : options.power_preference,
+            compatible_surface          : Some(surface),
+            force_fallback_adapter      : options.force_fallback_adapter,
+        }).await.ok_or(format!("Failed to find an appropriate adapter (backend {}, fallback adapter {})",
+            GPUInstanceOptions::backends_name(options.backends), options.force_fallback_adapter))
+    }
+
+    async fn create_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), String> {
+        let limits = wgpu::Limits::default();
+
+        // without it only the sample counts guaranteed by webgpu (1 and 4) can be used
+        let features = adapter.features() & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+
+        adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                features   : features,
+                limits     : limits,
+                label      : Some("GPUInstance"),
+            },
+            None,
+        ).await.map_err(|e| format!("Failed to create device: {}", e))
+    }
+
+    // the highest msaa sample count up to the requested one that can be rendered and resolved with the format
+    pub fn supported_sample_count(&self, format: wgpu::TextureFormat, requested: u32) -> u32 {
+        let features = self.adapter.get_texture_format_features(format);
+        let adapter_specific = self.device.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+        if !features.flags.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE) {
+            return 1;
+        }
+
+        [16, 8, 4, 2].into_iter()
+            .filter(|count| *count <= requested)
+            .find(|count| features.flags.sample_count_supported(*count) && (adapter_specific || *count == 4))
+            .unwrap_or(1)
+    }
+
+    pub fn surface(&self) -> Option<&wgpu::Surface> {
+        self.surface.as_ref()
     }
 
     pub fn device(&self) -> &wgpu::Device {
@@ -75,6 +258,10 @@ impl GPUInstance {
         &self.instance
     }
 
+    pub fn options(&self) -> &GPUInstanceOptions {
+        &self.options
+    }
+
     pub fn swapchain_mut(&mut self) -> Option<&mut crate::Swapchain> {
         self.swapchain.as_mut()
     }
@@ -83,14 +270,41 @@ impl GPUInstance {
         self.swapchain.as_ref()
     }
 
+    // zero sized configurations are skipped, wgpu does not accept them
     pub fn configure_surface(&self, config: &wgpu::SurfaceConfiguration) {
-        self.surface.configure(&self.device, config);
+        if config.width == 0 || config.height == 0 {
+            return;
+        }
+        if let Some(surface) = self.surface.as_ref() {
+            surface.configure(&self.device, config);
+        }
     }
 
     pub fn reconfigure_surface(&self) {
         if let Some(swapchain) = self.swapchain.as_ref() {
-            self.surface.configure(&self.device, swapchain.surface_config());
+            self.configure_surface(swapchain.surface_config());
+        }
+    }
+
+    // drops the surface so another one can be created for the same window, acquiring textures fails with Lost until then
+    pub fn release_surface(&mut self) {
+        self.surface = None;
+    }
+
+    // replaces a lost surface with a new one for the same window, the device and swapchain settings are kept
+    pub fn recreate_surface(&mut self, window: &crate::Window) -> Result<(), String> {
+        self.release_surface();
+        let surface = unsafe { self.instance.create_surface(window.winit_handle()) }.map_err(|e| format!("Failed to recreate surface: {}", e))?;
+        if !self.adapter.is_surface_supported(&surface) {
+            return Err("The adapter does not support the recreated surface".to_string());
+        }
+        self.surface = Some(surface);
+
+        if let Some(swapchain) = self.swapchain.as_mut() {
+            swapchain.resize(window.width(), window.height());
         }
+        self.reconfigure_surface();
+        Ok(())
     }
 
     pub fn encoder(&self, label: &str) -> wgpu::CommandEncoder {
@@ -99,16 +313,72 @@ impl GPUInstance {
         })
     }
 
+    // does not wait for the gpu, only lets finished work run its callbacks. use poll to block until it is done
     pub fn submit(&self, encoder: wgpu::CommandEncoder) {
         self.queue.submit(std::iter::once(encoder.finish()));
-        self.device.poll(wgpu::Maintain::Wait);
+        self.device.poll(wgpu::Maintain::Poll);
+    }
+
+    // runs the pipeline over size invocations in its own submission, bind groups are set in order from group 0
+    pub fn dispatch(&self, pipeline: &crate::ComputePipeline, bind_groups: &[&wgpu::BindGroup], size: (u32, u32, u32)) -> Result<(), String> {
+        let mut encoder = self.encoder("GPUInstance::dispatch.encoder");
+        self.encode_dispatch(&mut encoder, pipeline, bind_groups, size)?;
+        self.submit(encoder);
+        Ok(())
+    }
+
+    // like dispatch, recorded into an existing encoder so several passes can go in one submission
+    pub fn encode_dispatch(&self, encoder: &mut wgpu::CommandEncoder, pipeline: &crate::ComputePipeline, bind_groups: &[&wgpu::BindGroup], size: (u32, u32, u32)) -> Result<(), String> {
+        let workgroups = pipeline.workgroup_count(size);
+        let max_workgroups = self.device.limits().max_compute_workgroups_per_dimension;
+        if workgroups.0 > max_workgroups || workgroups.1 > max_workgroups || workgroups.2 > max_workgroups {
+            return Err(format!("Too many workgroups: {:?}, the limit is {} per dimension", workgroups, max_workgroups));
+        }
+        if workgroups.0 == 0 || workgroups.1 == 0 || workgroups.2 == 0 {
+            return Ok(());
+        }
+
+        let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label               : Some("GPUInstance::dispatch.cpass"),
+            timestamp_writes    : None,
+        });
+        cpass.set_pipeline(pipeline.pipeline());
+        for (index, bind_group) in bind_groups.iter().enumerate() {
+            cpass.set_bind_group(index as u32, bind_group, &[]);
+        }
+        cpass.dispatch_workgroups(workgroups.0, workgroups.1, workgroups.2);
+        Ok(())
     }
 
     pub fn submit_without_poll(&self, encoder: wgpu::CommandEncoder) {
         self.queue.submit(std::iter::once(encoder.finish()));
     }
 
+    // blocks until everything submitted so far is done
     pub fn poll(&self) {
         self.device.poll(wgpu::Maintain::Wait);
     }
+
+    // maps a MAP_READ buffer without blocking the caller, a helper thread waits on the device until the work
+    // submitted so far (the copy into the buffer included) is done. the thread lets go of the device before
+    // the result is sent, so the device never outlives an instance dropped right after the read
+    pub async fn map_read(&self, buffer: &wgpu::Buffer) -> Result<(), String> {
+        let (map_sender, map_receiver) = std::sync::mpsc::channel();
+        buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            let _ = map_sender.send(result);
+        });
+
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let device = self.device.clone();
+        std::thread::spawn(move || {
+            device.poll(wgpu::Maintain::Wait);
+            drop(device);
+            let _ = sender.send(map_receiver.recv());
+        });
+
+        receiver.await
+            .map_err(|e| format!("Failed to receive map result: {}", e))?
+            .map_err(|e| format!("Failed to receive map result: {}", e))?
+            .map_err(|e| format!("Failed to map buffer: {}", e))
+    }
 }
\ No newline at end of file
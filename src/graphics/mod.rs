@@ -7,17 +7,32 @@ pub use swapchain::*;
 mod pipeline;
 pub use pipeline::*;
 
+mod compute_pipeline;
+pub use compute_pipeline::*;
+
 mod ubo;
 pub use ubo::*;
 
 mod ssbo;
 pub use ssbo::*;
 
+mod render_target;
+pub use render_target::*;
+
+mod texture_array;
+pub use texture_array::*;
+
 mod texture_packer;
 pub use texture_packer::*;
 
 mod font;
 pub use font::*;
 
+mod blur_pass;
+pub use blur_pass::*;
+
 mod renderer;
-pub use renderer::*;
\ No newline at end of file
+pub use renderer::*;
+
+mod atlas_debug_view;
+pub use atlas_debug_view::*;
\ No newline at end of file
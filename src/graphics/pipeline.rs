@@ -1,72 +1,373 @@
+// how the fragment output is combined with the color target
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GraphicsPipelineBlendMode {
+    Replace,
+    Alpha,              // straight alpha
+    Premultiplied,      // color already multiplied by alpha
+    Additive,
+}
+
+impl GraphicsPipelineBlendMode {
+    pub fn blend_state(&self) -> Option<wgpu::BlendState> {
+        match self {
+            Self::Replace => None,
+            Self::Alpha => Some(wgpu::BlendState::ALPHA_BLENDING),
+            Self::Premultiplied => Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            Self::Additive => Some(wgpu::BlendState {
+                color   : wgpu::BlendComponent {
+                            src_factor  : wgpu::BlendFactor::SrcAlpha,
+                            dst_factor  : wgpu::BlendFactor::One,
+                            operation   : wgpu::BlendOperation::Add,
+                        },
+                alpha   : wgpu::BlendComponent {
+                            src_factor  : wgpu::BlendFactor::One,
+                            dst_factor  : wgpu::BlendFactor::One,
+                            operation   : wgpu::BlendOperation::Add,
+                        },
+            }),
+        }
+    }
+}
+
+// an owned vertex buffer layout, kept around so the pipeline can be recreated
+#[derive(Clone, Debug)]
+struct GraphicsPipelineVertexBuffer {
+    array_stride        : wgpu::BufferAddress,
+    step_mode           : wgpu::VertexStepMode,
+    attributes          : Vec<wgpu::VertexAttribute>,
+}
+
+// everything but the shader and the bind group layouts
+#[derive(Clone, Debug)]
+struct GraphicsPipelineConfig {
+    target_format       : wgpu::TextureFormat,
+    vertex_entry        : String,
+    fragment_entry      : Option<String>,
+    vertex_buffers      : Vec<GraphicsPipelineVertexBuffer>,
+    blend_mode          : GraphicsPipelineBlendMode,
+    topology            : wgpu::PrimitiveTopology,
+    strip_index_format  : Option<wgpu::IndexFormat>,
+    cull_mode           : Option<wgpu::Face>,
+    depth_stencil       : Option<wgpu::DepthStencilState>,
+    sample_count        : u32,
+    label               : Option<String>,
+}
+
+// a shader file watched for changes, only used in debug builds
+struct GraphicsPipelineShaderFile {
+    path                : String,
+    modified            : Option<std::time::SystemTime>,
+    last_check          : std::time::Instant,
+}
+
 pub struct GraphicsPipeline {
     layout          : wgpu::PipelineLayout,
     pipeline        : wgpu::RenderPipeline,
+    config          : GraphicsPipelineConfig,
+    shader_file     : Option<GraphicsPipelineShaderFile>,
 }
 
-impl GraphicsPipeline {
+// defaults match GraphicsPipeline::new: vs_main/fs_main, no vertex buffers, alpha blending, triangle list,
+// no culling, no depth and no msaa
+pub struct GraphicsPipelineBuilder<'a> {
+    shader_source       : String,
+    shader_path         : Option<String>,
+    bind_grp_layouts    : Vec<&'a wgpu::BindGroupLayout>,
+    config              : GraphicsPipelineConfig,
+}
 
-    pub async fn new(instance: &crate::GPUInstance, shader_source: String, target_format: wgpu::TextureFormat, bind_grp_layouts: Vec<&wgpu::BindGroupLayout>, label: Option<&str>) -> Result<Self, String> {
+impl<'a> GraphicsPipelineBuilder<'a> {
 
-        let shader = instance.device().create_shader_module(wgpu::ShaderModuleDescriptor {
-            label       : label,
-            source      : wgpu::ShaderSource::Wgsl(shader_source.into()),
+    pub fn bind_group_layouts(mut self, bind_grp_layouts: Vec<&'a wgpu::BindGroupLayout>) -> Self {
+        self.bind_grp_layouts = bind_grp_layouts;
+        self
+    }
+
+    // in debug builds the shader is read from this file instead (when it exists) and reloaded when it changes,
+    // see GraphicsPipeline::reload_if_changed. release builds always use the source given to builder
+    pub fn shader_path(mut self, path: &str) -> Self {
+        self.shader_path = Some(path.to_string());
+        self
+    }
+
+    pub fn vertex_entry(mut self, entry_point: &str) -> Self {
+        self.config.vertex_entry = entry_point.to_string();
+        self
+    }
+
+    // None leaves out the fragment stage and the color target, e.g. for depth only passes
+    pub fn fragment_entry(mut self, entry_point: Option<&str>) -> Self {
+        self.config.fragment_entry = entry_point.map(|entry_point| entry_point.to_string());
+        self
+    }
+
+    // buffers are bound in the order they are added
+    pub fn vertex_buffer(mut self, array_stride: wgpu::BufferAddress, step_mode: wgpu::VertexStepMode, attributes: &[wgpu::VertexAttribute]) -> Self {
+        self.config.vertex_buffers.push(GraphicsPipelineVertexBuffer {
+            array_stride        : array_stride,
+            step_mode           : step_mode,
+            attributes          : attributes.to_vec(),
         });
+        self
+    }
+
+    pub fn blend_mode(mut self, blend_mode: GraphicsPipelineBlendMode) -> Self {
+        self.config.blend_mode = blend_mode;
+        self
+    }
+
+    pub fn topology(mut self, topology: wgpu::PrimitiveTopology) -> Self {
+        self.config.topology = topology;
+        self
+    }
+
+    // only for strip topologies drawn with indices, has to match the index buffer format
+    pub fn strip_index_format(mut self, strip_index_format: Option<wgpu::IndexFormat>) -> Self {
+        self.config.strip_index_format = strip_index_format;
+        self
+    }
+
+    pub fn cull_mode(mut self, cull_mode: Option<wgpu::Face>) -> Self {
+        self.config.cull_mode = cull_mode;
+        self
+    }
+
+    // the render pass needs a depth attachment of the same format
+    pub fn depth(mut self, format: wgpu::TextureFormat, compare: wgpu::CompareFunction, write_enabled: bool) -> Self {
+        self.config.depth_stencil = Some(wgpu::DepthStencilState {
+            format                  : format,
+            depth_write_enabled     : write_enabled,
+            depth_compare           : compare,
+            stencil                 : wgpu::StencilState::default(),
+            bias                    : wgpu::DepthBiasState::default(),
+        });
+        self
+    }
+
+    // has to match the color (and depth) targets the pipeline is used with
+    pub fn sample_count(mut self, sample_count: u32) -> Self {
+        self.config.sample_count = sample_count;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.config.label = Some(label.to_string());
+        self
+    }
+
+    // shader and pipeline validation errors are returned instead of panicking
+    pub async fn build(self, instance: &crate::GPUInstance) -> Result<GraphicsPipeline, String> {
+        if !self.config.sample_count.is_power_of_two() || self.config.sample_count > 16 {
+            return Err(format!("Invalid sample count: {}", self.config.sample_count));
+        }
+
+        let mut shader_source = self.shader_source;
+        let mut shader_file = None;
+        if cfg!(debug_assertions) {
+            if let Some(path) = self.shader_path {
+                match std::fs::read_to_string(&path) {
+                    Ok(source) => {
+                        log::info!("Loading shader from {}", path);
+                        shader_source = source;
+                        shader_file = Some(GraphicsPipelineShaderFile {
+                            modified            : GraphicsPipeline::modified_time(&path),
+                            path                : path,
+                            last_check          : std::time::Instant::now(),
+                        });
+                    },
+                    Err(e) => log::warn!("Failed to read shader {}, using the embedded one: {}", path, e),
+                }
+            }
+        }
+
+        GraphicsPipeline::validate_wgsl(&shader_source)?;
 
         let layout = instance.device().create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label                   : label.clone(),
-            bind_group_layouts      : &bind_grp_layouts,
+            label                   : self.config.label.as_deref(),
+            bind_group_layouts      : &self.bind_grp_layouts,
             push_constant_ranges    : &[],
         });
 
-        let pipeline = instance.device().create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let pipeline = GraphicsPipeline::create_pipeline(instance, &self.config, &layout, shader_source).await?;
+
+        Ok(GraphicsPipeline {
+            layout          : layout,
+            pipeline        : pipeline,
+            config          : self.config,
+            shader_file     : shader_file,
+        })
+    }
+}
+
+impl GraphicsPipeline {
+
+    // sample_count has to match the color target the pipeline is used with
+    pub async fn new(instance: &crate::GPUInstance, shader_source: String, target_format: wgpu::TextureFormat, bind_grp_layouts: Vec<&wgpu::BindGroupLayout>, sample_count: u32, label: Option<&str>) -> Result<Self, String> {
+        let mut builder = Self::builder(shader_source, target_format)
+            .bind_group_layouts(bind_grp_layouts)
+            .sample_count(sample_count);
+        if let Some(label) = label {
+            builder = builder.label(label);
+        }
+        builder.build(instance).await
+    }
+
+    pub fn builder<'a>(shader_source: String, target_format: wgpu::TextureFormat) -> GraphicsPipelineBuilder<'a> {
+        GraphicsPipelineBuilder {
+            shader_source       : shader_source,
+            shader_path         : None,
+            bind_grp_layouts    : Vec::new(),
+            config              : GraphicsPipelineConfig {
+                                    target_format       : target_format,
+                                    vertex_entry        : "vs_main".to_string(),
+                                    fragment_entry      : Some("fs_main".to_string()),
+                                    vertex_buffers      : Vec::new(),
+                                    blend_mode          : GraphicsPipelineBlendMode::Alpha,
+                                    topology            : wgpu::PrimitiveTopology::TriangleList,
+                                    strip_index_format  : None,
+                                    cull_mode           : None,
+                                    depth_stencil       : None,
+                                    sample_count        : 1,
+                                    label               : None,
+                                },
+        }
+    }
+
+    // checks the watched shader file (at most twice a second) and swaps in the new pipeline when it changed.
+    // on any error the current pipeline is kept and the error logged, returns whether the pipeline was replaced
+    pub async fn reload_if_changed(&mut self, instance: &crate::GPUInstance) -> bool {
+        let shader_file = match self.shader_file.as_mut() {
+            Some(shader_file) => shader_file,
+            None => return false,
+        };
+
+        if shader_file.last_check.elapsed() < std::time::Duration::from_millis(500) {
+            return false;
+        }
+        shader_file.last_check = std::time::Instant::now();
+
+        let modified = Self::modified_time(&shader_file.path);
+        if modified.is_none() || modified == shader_file.modified {
+            return false;
+        }
+        shader_file.modified = modified;
+        let path = shader_file.path.clone();
+
+        let result = match std::fs::read_to_string(&path) {
+            Ok(source) => match Self::validate_wgsl(&source) {
+                Ok(_) => Self::create_pipeline(instance, &self.config, &self.layout, source).await,
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(format!("Failed to read shader: {}", e)),
+        };
+
+        match result {
+            Ok(pipeline) => {
+                log::info!("Reloaded shader {}", path);
+                self.pipeline = pipeline;
+                true
+            },
+            Err(e) => {
+                log::error!("Failed to reload shader {}, keeping the previous one: {}", path, e);
+                false
+            }
+        }
+    }
+
+    pub fn layout(&self) -> &wgpu::PipelineLayout {
+        &self.layout
+    }
+
+    pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.config.sample_count
+    }
+
+    pub fn depth_format(&self) -> Option<wgpu::TextureFormat> {
+        self.config.depth_stencil.as_ref().map(|depth_stencil| depth_stencil.format)
+    }
+
+    // parses and validates with naga, so a broken shader is reported with its source location instead of
+    // failing inside wgpu
+    pub(crate) fn validate_wgsl(source: &str) -> Result<naga::Module, String> {
+        let module = naga::front::wgsl::parse_str(source).map_err(|e| e.emit_to_string(source))?;
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+            .validate(&module)
+            .map_err(|e| format!("Shader validation failed: {}", e.into_inner()))?;
+        Ok(module)
+    }
+
+    fn modified_time(path: &str) -> Option<std::time::SystemTime> {
+        std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    async fn create_pipeline(instance: &crate::GPUInstance, config: &GraphicsPipelineConfig, layout: &wgpu::PipelineLayout, shader_source: String) -> Result<wgpu::RenderPipeline, String> {
+        let label = config.label.as_deref();
+        let device = instance.device();
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label       : label,
+            source      : wgpu::ShaderSource::Wgsl(shader_source.into()),
+        });
+
+        let targets = [Some(wgpu::ColorTargetState {
+            format      : config.target_format,
+            blend       : config.blend_mode.blend_state(),
+            write_mask  : wgpu::ColorWrites::ALL,
+        })];
+
+        let vertex_buffers = config.vertex_buffers.iter().map(|buffer| wgpu::VertexBufferLayout {
+            array_stride    : buffer.array_stride,
+            step_mode       : buffer.step_mode,
+            attributes      : &buffer.attributes,
+        }).collect::<Vec<_>>();
+
+        // wgpu rejects a strip index format on list topologies
+        let strip_index_format = match config.topology {
+            wgpu::PrimitiveTopology::LineStrip | wgpu::PrimitiveTopology::TriangleStrip => config.strip_index_format,
+            _ => None,
+        };
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: label,
-            layout: Some(&layout),
+            layout: Some(layout),
             vertex: wgpu::VertexState {
                 module          : &shader,
-                entry_point     : "vs_main",
-                buffers         : &[],
+                entry_point     : &config.vertex_entry,
+                buffers         : &vertex_buffers,
             },
-            fragment: Some(wgpu::FragmentState {
+            fragment: config.fragment_entry.as_ref().map(|entry_point| wgpu::FragmentState {
                 module          : &shader,
-                entry_point     : "fs_main",
-                targets         : &[Some(wgpu::ColorTargetState {
-                                    format      : target_format,
-                                    blend       : Some(wgpu::BlendState::ALPHA_BLENDING),
-                                    write_mask  : wgpu::ColorWrites::ALL,
-                                })],
+                entry_point     : entry_point,
+                targets         : &targets,
             }),
             primitive: wgpu::PrimitiveState {
-                topology            : wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format  : None,
+                topology            : config.topology,
+                strip_index_format  : strip_index_format,
                 front_face          : wgpu::FrontFace::Ccw,
-                cull_mode           : None,
+                cull_mode           : config.cull_mode,
                 polygon_mode        : wgpu::PolygonMode::Fill,
                 unclipped_depth     : false,
                 conservative        : false,
             },
-            depth_stencil: None,
+            depth_stencil: config.depth_stencil.clone(),
             multisample: wgpu::MultisampleState {
-                count                      : 1,
+                count                      : config.sample_count,
                 mask                       : !0,
                 alpha_to_coverage_enabled  : false,
             },
             multiview: None,
         });
 
-        let obj = Self {
-            layout          : layout,
-            pipeline        : pipeline,
-        };
-
-        Ok(obj)
-    }
-
-    pub fn layout(&self) -> &wgpu::PipelineLayout {
-        &self.layout
-    }
+        if let Some(e) = device.pop_error_scope().await {
+            return Err(format!("Failed to create pipeline {}: {}", label.unwrap_or(""), e));
+        }
 
-    pub fn pipeline(&self) -> &wgpu::RenderPipeline {
-        &self.pipeline
+        Ok(pipeline)
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,116 @@
+// an offscreen color target that can be rendered to instead of a swapchain texture and read back
+pub struct RenderTarget {
+    texture         : wgpu::Texture,
+    format          : wgpu::TextureFormat,
+    width           : u32,
+    height          : u32,
+}
+
+impl RenderTarget {
+
+    pub async fn new(instance: &crate::GPUInstance, width: u32, height: u32, format: wgpu::TextureFormat, label: Option<&str>) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("Invalid render target size: {}x{}", width, height));
+        }
+
+        match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb |
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {},
+            _ => return Err(format!("Unsupported render target format: {:?}", format)),
+        }
+
+        let texture = instance.device().create_texture(&wgpu::TextureDescriptor {
+            label               : label,
+            size                : wgpu::Extent3d {
+                                    width                   : width,
+                                    height                  : height,
+                                    depth_or_array_layers   : 1,
+                                },
+            mip_level_count     : 1,
+            sample_count        : 1,
+            dimension           : wgpu::TextureDimension::D2,
+            format              : format,
+            usage               : wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats        : &[],
+        });
+
+        Ok(Self {
+            texture         : texture,
+            format          : format,
+            width           : width,
+            height          : height,
+        })
+    }
+
+    pub fn create_view(&self) -> wgpu::TextureView {
+        self.texture.create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    // resolves once the gpu is done with everything submitted so far
+    pub async fn read_back(&self, instance: &crate::GPUInstance) -> Result<image::RgbaImage, String> {
+        let unpadded_bytes_per_row = self.width * 4;
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(alignment) * alignment;
+
+        let buffer = instance.device().create_buffer(&wgpu::BufferDescriptor {
+            label               : Some("RenderTarget::read_back.buffer"),
+            size                : (padded_bytes_per_row * self.height) as u64,
+            usage               : wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation  : false,
+        });
+
+        let mut encoder = instance.encoder("RenderTarget::read_back.encoder");
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture     : &self.texture,
+                mip_level   : 0,
+                origin      : wgpu::Origin3d::ZERO,
+                aspect      : wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer      : &buffer,
+                layout      : wgpu::ImageDataLayout {
+                                offset          : 0,
+                                bytes_per_row   : Some(padded_bytes_per_row),
+                                rows_per_image  : Some(self.height),
+                            },
+            },
+            wgpu::Extent3d {
+                width                   : self.width,
+                height                  : self.height,
+                depth_or_array_layers   : 1,
+            },
+        );
+        instance.submit(encoder);
+
+        instance.map_read(&buffer).await?;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * self.height) as usize);
+        {
+            let data = buffer.slice(..).get_mapped_range();
+            for row in data.chunks(padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        buffer.unmap();
+
+        if matches!(self.format, wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb) {
+            pixels.chunks_mut(4).for_each(|pixel| pixel.swap(0, 2));
+        }
+
+        image::RgbaImage::from_raw(self.width, self.height, pixels).ok_or("Failed to create image".to_string())
+    }
+
+}
@@ -1,43 +1,106 @@
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]  #[repr(C)]
 struct RendererItem {
     offset_scale            : [f32; 4], // (x, y), (scale_x, scale_y)
-    texture_transform       : [f32; 4], // (x, y), (scale_x, scale_y) / color (r, g, b, a)
+    texture_transform       : [f32; 4], // (x, y), (scale_x, scale_y)
+    color                   : [f32; 4], // (r, g, b, a), tints the texture if any
     mask                    : [f32; 4], // (x, y), (scale_x, scale_y)
-    border_radius           : [f32; 4], // (top_left, top_right, bottom_right, bottom_left)
-    border_radius_mask      : [f32; 4], // (top_left, top_right, bottom_right, bottom_left)
-    data0                   : [f32; 4], // (depth, rotation, unused, unused)
-    meta0                   : [f32; 4], // (type, tex_layer, unused, unused)
+    border_radius           : [f32; 4], // (top_left, top_right, bottom_right, bottom_left) in pixels
+    border_radius_mask      : [f32; 4], // (top_left, top_right, bottom_right, bottom_left) in pixels
+    data0                   : [f32; 4], // (depth, rotation, stroke_width / shadow_blur / thickness / backdrop_blur, unused)
+    data1                   : [f32; 4], // type specific, shadow shape / gradient / line / arc parameters
+    color1                  : [f32; 4], // (r, g, b, a), gradient end color
+    meta0                   : [f32; 4], // (type, tex_layer, layer, unused)
+    transform0              : [f32; 4], // (m00, m01, m10, m11), column major 2x2 part of the affine transform
+    transform1              : [f32; 4], // (translate_x, translate_y, unused, unused)
+}
+
+// colors are (r, g, b, a), positions are relative to the filled rect with (0, 0) at the bottom left
+#[derive(Copy, Clone, Debug)]
+pub enum RendererGradient {
+    Linear {
+        from            : (f32, f32, f32, f32),
+        to              : (f32, f32, f32, f32),
+        start           : (f32, f32),
+        end             : (f32, f32),
+    },
+    Radial {
+        from            : (f32, f32, f32, f32),
+        to              : (f32, f32, f32, f32),
+        center          : (f32, f32),
+        radius          : f32,      // in pixels
+    },
+}
+
+// items are drawn layer by layer, so a tooltip always ends up above the content no matter the call order.
+// the order only holds within a flush, anything flushed before stays below later items regardless of their layer
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RendererLayer {
+    Background,
+    #[default]
+    Content,
+    Overlay,
+    Tooltip,
+}
+
+// where textp finds glyphs, metrics come from the font and bitmaps from the packer as "<prefix><char>"
+#[derive(Copy, Clone)]
+pub struct RendererGlyphs<'a> {
+    pub font        : &'a crate::Font,
+    pub packer      : &'a crate::TexturePacker,
+    pub prefix      : &'a str,
 }
 
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]  #[repr(C)]
 struct RendererGPUData {
-    data0           : [f32; 4], // aspect_ratio, unused, unused, unused
+    data0           : [f32; 4], // scale_factor, frame_width, frame_height, unused
 }
 
 struct RendererFrameConfig {
     num_items           : usize,
     depth               : f32,
-    texture             : Option<(f32, f32, f32, f32)>,
+    layer               : RendererLayer,
+    texture             : Option<crate::TexturePackerRegion>,
     color               : (f32, f32, f32, f32),
     mask                : (f32, f32, f32, f32),    
     border_radius       : (f32, f32, f32, f32),
     border_radius_mask  : (f32, f32, f32, f32),
-    frame_res           : (f32, f32),
+    frame_res           : (f32, f32),         // in physical pixels
+    rotation            : f32,
+    transform           : cgmath::Matrix3<f32>,     // affine, in physical pixels
+    transform_stack     : Vec<cgmath::Matrix3<f32>>,
+    clip_stack          : Vec<((f32, f32, f32, f32), (f32, f32, f32, f32))>,    // (mask, border_radius_mask)
 }
 
 pub struct Renderer{
     pipeline        : crate::GraphicsPipeline,   
     gpu_data        : crate::UniformBuffer<RendererGPUData>,
     items           : Vec<RendererItem>,
-    items_gpu       : crate::ShaderStorageBuffer,
+    items_gpu       : crate::ShaderStorageBuffer<RendererItem>,
+    atlas_coverage  : crate::TextureArray,
+    atlas_color     : crate::TextureArray,
+    atlas_sampler   : wgpu::Sampler,
+    atlas_layout    : wgpu::BindGroupLayout,
+    atlas_bind_group: wgpu::BindGroup,
     frame_config    : RendererFrameConfig,
     target_view     : Option<wgpu::TextureView>,
+    target_cleared  : bool,
+    scale_factor    : f32,
+    target_format   : wgpu::TextureFormat,
+    sample_count    : u32,
+    msaa_target     : Option<(wgpu::TextureView, (u32, u32))>,    // (view, size), only with sample_count > 1
+    msaa_holds_target: bool,    // the msaa texture has what the target has, so later flushes can draw on top of it
+    blur_pass       : crate::BlurPass,
+    scene_target    : Option<(wgpu::TextureView, (u32, u32))>,    // (view, size), drawn into instead of the target when there are backdrops
+    draw_to_scene   : bool,
+    backdrop_layout : wgpu::BindGroupLayout,
+    backdrop_bind_group: wgpu::BindGroup,
+    backdrop_generation: u64,   // blur pass generation the bind group was created for
 }
 
 impl Default for RendererGPUData {
     fn default() -> Self {
         Self {
-            data0       : [1.0, 0.0, 0.0, 1.0],
+            data0       : [1.0, 1.0, 1.0, 1.0],
         }
     }
 }
@@ -47,12 +110,17 @@ impl Default for RendererFrameConfig {
         Self {
             num_items           : 0,
             depth               : 0.0,
+            layer               : RendererLayer::Content,
             texture             : None,
             color               : (0.0, 0.0, 0.0, 1.0),
             mask                : (0.0, 0.0, 1.0, 1.0),
             border_radius       : (0.0, 0.0, 0.0, 0.0),
             border_radius_mask  : (0.0, 0.0, 0.0, 0.0),
             frame_res           : (800.0, 600.0),
+            rotation            : 0.0,
+            transform           : cgmath::Matrix3::new(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0),
+            transform_stack     : Vec::new(),
+            clip_stack          : Vec::new(),
         }
     }
 }
@@ -61,28 +129,138 @@ impl Default for RendererFrameConfig {
 impl Renderer{
     
     const MAX_ITEMS: usize = 1024 * 64;
+    const INITIAL_ITEMS: usize = 1024;
+
+    const ITEM_TYPE_COLOR: f32 = 0.0;
+    const ITEM_TYPE_TEXTURE_RGBA: f32 = 1.0;
+    const ITEM_TYPE_TEXTURE_COVERAGE: f32 = 2.0;
+    const ITEM_TYPE_STROKE: f32 = 3.0;
+    const ITEM_TYPE_SHADOW: f32 = 4.0;
+    const ITEM_TYPE_LINEAR_GRADIENT: f32 = 5.0;
+    const ITEM_TYPE_RADIAL_GRADIENT: f32 = 6.0;
+    const ITEM_TYPE_ELLIPSE: f32 = 7.0;
+    const ITEM_TYPE_LINE: f32 = 8.0;
+    const ITEM_TYPE_ARC: f32 = 9.0;
+    const ITEM_TYPE_BACKDROP: f32 = 10.0;
     
     
-    pub async fn new(instance: &crate::GPUInstance, target_format: wgpu::TextureFormat) -> Result<Self, String> {
+    // sample_count is lowered to what the adapter supports for the target format, 1 disables msaa
+    pub async fn new(instance: &crate::GPUInstance, target_format: wgpu::TextureFormat, sample_count: u32) -> Result<Self, String> {
+        let supported_sample_count = instance.supported_sample_count(target_format, sample_count.max(1));
+        if supported_sample_count != sample_count {
+            log::warn!("MSAA sample count {} is not supported for {:?}, using {}", sample_count, target_format, supported_sample_count);
+        }
+        let sample_count = supported_sample_count;
         
         let gpu_data = crate::UniformBuffer::<RendererGPUData>::new(instance, Some("RendererGPUData")).await?;
-        let items_gpu = crate::ShaderStorageBuffer::new(instance, Self::MAX_ITEMS * std::mem::size_of::<RendererItem>(), Some("RendererItems")).await?;
-        let pipeline = crate::GraphicsPipeline::new(instance,
-            include_str!("../assets/shaders/renderer.wgsl").to_string(),
-            target_format,
-            vec![
-            &gpu_data.layout(),
-            &items_gpu.layout(),
+        // grows with the item count up to MAX_ITEMS
+        let items_gpu = crate::ShaderStorageBuffer::<RendererItem>::new(instance, Self::INITIAL_ITEMS, Some("RendererItems")).await?;
+
+        // placeholders until the atlases are uploaded
+        let atlas_coverage = crate::TextureArray::new(instance, 1, 1, 1, 1, crate::TexturePackerFormat::R8.texture_format(), Some("RendererAtlasCoverage")).await?;
+        let atlas_color = crate::TextureArray::new(instance, 1, 1, 1, 1, crate::TexturePackerFormat::Rgba8.texture_format(), Some("RendererAtlasColor")).await?;
+
+        let atlas_sampler = instance.device().create_sampler(&wgpu::SamplerDescriptor {
+            label               : Some("RendererAtlasSampler"),
+            address_mode_u      : wgpu::AddressMode::ClampToEdge,
+            address_mode_v      : wgpu::AddressMode::ClampToEdge,
+            address_mode_w      : wgpu::AddressMode::ClampToEdge,
+            mag_filter          : wgpu::FilterMode::Linear,
+            min_filter          : wgpu::FilterMode::Linear,
+            mipmap_filter       : wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let atlas_texture_entry = |binding: u32| wgpu::BindGroupLayoutEntry {
+            binding     : binding,
+            visibility  : wgpu::ShaderStages::FRAGMENT,
+            ty          : wgpu::BindingType::Texture {
+                            sample_type     : wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension  : wgpu::TextureViewDimension::D2Array,
+                            multisampled    : false,
+                        },
+            count       : None,
+        };
+
+        let atlas_layout = instance.device().create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label       : Some("RendererAtlas"),
+            entries     : &[
+                atlas_texture_entry(0),
+                atlas_texture_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding     : 2,
+                    visibility  : wgpu::ShaderStages::FRAGMENT,
+                    ty          : wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count       : None,
+                },
             ],
-            Some("MainRendererPipeline")).await?;
+        });
+
+        let atlas_bind_group = Self::create_atlas_bind_group(instance, &atlas_layout, &atlas_coverage, &atlas_color, &atlas_sampler);
+
+        // the blurred frame behind backdrop items, a placeholder until the first blur
+        let blur_pass = crate::BlurPass::new(instance, target_format).await?;
+        let backdrop_layout = instance.device().create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label       : Some("RendererBackdrop"),
+            entries     : &[
+                wgpu::BindGroupLayoutEntry {
+                    binding     : 0,
+                    visibility  : wgpu::ShaderStages::FRAGMENT,
+                    ty          : wgpu::BindingType::Texture {
+                                    sample_type     : wgpu::TextureSampleType::Float { filterable: true },
+                                    view_dimension  : wgpu::TextureViewDimension::D2,
+                                    multisampled    : false,
+                                },
+                    count       : None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding     : 1,
+                    visibility  : wgpu::ShaderStages::FRAGMENT,
+                    ty          : wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count       : None,
+                },
+            ],
+        });
+        let backdrop_placeholder = Self::create_color_target(instance, target_format, (1, 1), 1, "RendererBackdropPlaceholder");
+        let backdrop_bind_group = Self::create_backdrop_bind_group(instance, &backdrop_layout, &backdrop_placeholder, &atlas_sampler);
+
+        // debug builds read the shader from the source tree so it can be edited while running, see reload_shaders
+        let pipeline = crate::GraphicsPipeline::builder(include_str!("../assets/shaders/renderer.wgsl").to_string(), target_format)
+            .shader_path(concat!(env!("CARGO_MANIFEST_DIR"), "/src/assets/shaders/renderer.wgsl"))
+            .bind_group_layouts(vec![
+                &gpu_data.layout(),
+                &items_gpu.layout(),
+                &atlas_layout,
+                &backdrop_layout,
+            ])
+            .sample_count(sample_count)
+            .label("MainRendererPipeline")
+            .build(instance).await?;
             
             let obj = Self {
                 pipeline        : pipeline,
                 gpu_data        : gpu_data,
                 items           : vec![],
                 items_gpu       : items_gpu,
+                atlas_coverage  : atlas_coverage,
+                atlas_color     : atlas_color,
+                atlas_sampler   : atlas_sampler,
+                atlas_layout    : atlas_layout,
+                atlas_bind_group: atlas_bind_group,
                 frame_config    : RendererFrameConfig::default(),
                 target_view     : None,
+                target_cleared  : false,
+                scale_factor    : 1.0,
+                target_format   : target_format,
+                sample_count    : sample_count,
+                msaa_target     : None,
+                msaa_holds_target: false,
+                blur_pass       : blur_pass,
+                scene_target    : None,
+                draw_to_scene   : false,
+                backdrop_layout : backdrop_layout,
+                backdrop_bind_group: backdrop_bind_group,
+                backdrop_generation: 0,
             };
             
             Ok(obj)
@@ -92,54 +270,191 @@ impl Renderer{
             self.items.clear();
             self.frame_config = RendererFrameConfig::default();
             self.target_view = None;
+            self.target_cleared = false;
+            self.msaa_holds_target = false;
             
             Ok(())
         }
         
+        // draws everything queued since the last flush on top of what is already in the target, layers and depth only
+        // order the items of this flush. the target is only cleared by the first flush after set_target_view
         pub fn flush(&mut self, instance: &crate::GPUInstance) -> Result<(), String> {
             self.frame_config.num_items = self.items.len();
-            self.items_gpu.upload(instance, bytemuck::cast_slice(&self.items))?;
-            self.items.clear();
 
             // flush here
             if self.target_view.is_none() {
+                self.items.clear();
                 return Err("No target view!".to_string());
             }
 
             self.gpu_data.upload(instance);
+            self.update_msaa_target(instance);
 
-            let mut encoder = instance.encoder("Renderer::flush.encoder");
-            let target_view = self.target_view.as_ref().unwrap();
-            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                color_attachments: &[
-                    Some(wgpu::RenderPassColorAttachment {
-                        view: &target_view,
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Clear( wgpu::Color {r: 0.0, g: 0.0, b: 0.0, a: 0.0}),
-                            store:  wgpu::StoreOp::Store,
-                        }
-                    })
-                ],
-                depth_stencil_attachment: None,
-                label: Some("Renderer::flush.rpass"),
-                occlusion_query_set: None,
-                timestamp_writes: None,
+            // there is no depth attachment, items are blended back to front instead: by layer, then from the
+            // farthest depth to the nearest, the stable sort keeps the submission order for equal keys
+            self.items.sort_by(|a, b| {
+                a.meta0[2].total_cmp(&b.meta0[2]).then(b.data0[0].total_cmp(&a.data0[0]))
             });
 
-            rpass.set_pipeline(&self.pipeline.pipeline());
-            rpass.set_bind_group(0, &self.gpu_data.bind_group(), &[]);
-            rpass.set_bind_group(1, &self.items_gpu.bind_group(), &[]);
-            rpass.draw(0..6, 0..self.frame_config.num_items as u32);
-            drop(rpass);
+            // backdrops need to sample what was drawn before them, which is not possible with the target (a swapchain
+            // texture can not be bound), so frames with backdrops are drawn into a scene texture and blitted at the end.
+            // everything is drawn with alpha blending onto a transparent clear, so the scene ends up premultiplied.
+            // the same goes for msaa once the target has content the msaa texture does not (after a scene was blitted),
+            // loading the msaa texture would resolve stale content over it
+            let has_backdrops = self.items.iter().any(|item| item.meta0[0] == Self::ITEM_TYPE_BACKDROP);
+            let msaa_stale = self.msaa_target.is_some() && !self.items.is_empty() && self.target_cleared && !self.msaa_holds_target;
+            self.draw_to_scene = has_backdrops || msaa_stale;
+            let target_cleared = self.target_cleared;
+            if self.draw_to_scene {
+                self.update_scene_target(instance);
+                self.target_cleared = false;
+            }
+
+            // the items before each backdrop are drawn first, then blurred for it, only behind the backdrop.
+            // a run of backdrops with the same radius shares one blur, unless one of them is drawn where a later one
+            // reads, which has to see it
+            let mut start = 0;
+            let mut index = 0;
+            while index < self.items.len() {
+                if self.items[index].meta0[0] != Self::ITEM_TYPE_BACKDROP {
+                    index += 1;
+                    continue;
+                }
+
+                self.draw_items(instance, start, index)?;
+                start = index;
+
+                let radius = self.items[index].data0[2];
+                let reach = crate::BlurPass::reach(radius);
+                let mut regions = vec![self.item_bounds(&self.items[index])];
+                index += 1;
+                while index < self.items.len() && self.items[index].meta0[0] == Self::ITEM_TYPE_BACKDROP && self.items[index].data0[2] == radius {
+                    let bounds = self.item_bounds(&self.items[index]);
+                    if regions.iter().any(|region| Self::regions_overlap(*region, bounds, reach)) {
+                        break;
+                    }
+                    regions.push(bounds);
+                    index += 1;
+                }
+
+                let (scene_view, scene_size) = self.scene_target.as_ref().unwrap();
+                self.blur_pass.blur(instance, scene_view, *scene_size, radius, &regions)?;
+                if self.blur_pass.generation() != self.backdrop_generation {
+                    self.backdrop_generation = self.blur_pass.generation();
+                    self.backdrop_bind_group = Self::create_backdrop_bind_group(instance, &self.backdrop_layout, self.blur_pass.output_view().unwrap(), &self.atlas_sampler);
+                }
+            }
+            self.draw_items(instance, start, self.items.len())?;
+
+            if self.draw_to_scene {
+                let (scene_view, _) = self.scene_target.as_ref().unwrap();
+                self.blur_pass.blit(instance, scene_view, self.target_view.as_ref().unwrap(), !target_cleared)?;
+                self.draw_to_scene = false;
+                self.target_cleared = true;
+            }
 
-            instance.submit(encoder);
+            self.items.clear();
+
+            Ok(())
+        }
+
+        // the storage buffer is kept to MAX_ITEMS, anything beyond that goes in further draws,
+        // each one submitted on its own so the next upload does not overwrite the previous one
+        fn draw_items(&mut self, instance: &crate::GPUInstance, start: usize, end: usize) -> Result<(), String> {
+            let batch_count = (end - start).div_ceil(Self::MAX_ITEMS);
+            for batch in 0..batch_count.max(1) {
+                let batch_start = start + batch * Self::MAX_ITEMS;
+                let batch_end = (batch_start + Self::MAX_ITEMS).min(end);
+                let batch_items = &self.items[batch_start..batch_end];
+
+                if batch_items.is_empty() && self.target_cleared {
+                    break;
+                }
+
+                self.items_gpu.upload(instance, batch_items)?;
+
+                let load = if self.target_cleared {
+                    wgpu::LoadOp::Load
+                } else {
+                    wgpu::LoadOp::Clear( wgpu::Color {r: 0.0, g: 0.0, b: 0.0, a: 0.0})
+                };
+
+                let mut encoder = instance.encoder("Renderer::flush.encoder");
+                let target_view = if self.draw_to_scene {
+                    &self.scene_target.as_ref().unwrap().0
+                } else {
+                    self.target_view.as_ref().unwrap()
+                };
+
+                // with msaa everything is drawn into the multisampled texture and resolved into the target,
+                // it is stored as well so later batches can keep drawing on top of it
+                let (view, resolve_target) = match self.msaa_target.as_ref() {
+                    Some((msaa_view, _)) => (msaa_view, Some(target_view)),
+                    None => (target_view, None),
+                };
+                let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    color_attachments: &[
+                        Some(wgpu::RenderPassColorAttachment {
+                            view: view,
+                            resolve_target: resolve_target,
+                            ops: wgpu::Operations {
+                                load: load,
+                                store:  wgpu::StoreOp::Store,
+                            }
+                        })
+                    ],
+                    depth_stencil_attachment: None,
+                    label: Some("Renderer::flush.rpass"),
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+
+                rpass.set_pipeline(&self.pipeline.pipeline());
+                rpass.set_bind_group(0, &self.gpu_data.bind_group(), &[]);
+                rpass.set_bind_group(1, &self.items_gpu.bind_group(), &[]);
+                rpass.set_bind_group(2, &self.atlas_bind_group, &[]);
+                rpass.set_bind_group(3, &self.backdrop_bind_group, &[]);
+                rpass.draw(0..6, 0..batch_items.len() as u32);
+                drop(rpass);
+
+                instance.submit(encoder);
+                self.target_cleared = true;
+                self.msaa_holds_target = self.msaa_target.is_some() && !self.draw_to_scene;
+            }
 
             Ok(())
         }
 
         pub fn set_target_view(&mut self, view: wgpu::TextureView) {
             self.target_view = Some(view);
+            self.target_cleared = false;
+            self.msaa_holds_target = false;
+        }
+
+        // uploads all layers (and mipmaps) of the packer into the atlas matching its format
+        pub async fn upload_atlas(&mut self, instance: &crate::GPUInstance, packer: &crate::TexturePacker) -> Result<(), String> {
+            let format = packer.format();
+            let (width, height, depth) = packer.size();
+            let mip_level_count = packer.mip_level_count();
+
+            let atlas = match format {
+                crate::TexturePackerFormat::R8 => &mut self.atlas_coverage,
+                crate::TexturePackerFormat::Rgba8 => &mut self.atlas_color,
+            };
+
+            if atlas.size() != (width, height, depth) || atlas.mip_level_count() != mip_level_count {
+                *atlas = crate::TextureArray::new(instance, width, height, depth, mip_level_count, format.texture_format(), Some(&format!("RendererAtlas.{}", format.name()))).await?;
+            }
+
+            for layer in 0..depth {
+                for level in 0..mip_level_count {
+                    atlas.upload_layer(instance, layer, level, packer.mip_data(layer, level).ok_or("Missing layer data!")?)?;
+                }
+            }
+
+            self.atlas_bind_group = Self::create_atlas_bind_group(instance, &self.atlas_layout, &self.atlas_coverage, &self.atlas_color, &self.atlas_sampler);
+
+            Ok(())
         }
         
         
@@ -148,66 +463,563 @@ impl Renderer{
             Ok(())
         }
         
+        // the size of the target in physical pixels
         pub fn set_frame_res(&mut self, frame_res: (u32, u32)) {
             self.frame_config.frame_res = (frame_res.0 as f32, frame_res.1 as f32);
-            self.gpu_data.data_mut().data0[0] = frame_res.0 as f32 / frame_res.1 as f32;
+            self.gpu_data.data_mut().data0[1] = frame_res.0 as f32;
+            self.gpu_data.data_mut().data0[2] = frame_res.1 as f32;
+        }
+
+        // all pixel values given to the renderer (the *p methods, radii, stroke widths, blurs, translations) are
+        // logical pixels, multiplied by the scale factor of the window. it persists across frames
+        pub fn set_scale_factor(&mut self, scale_factor: f32) {
+            self.scale_factor = if scale_factor > 0.0 { scale_factor } else { 1.0 };
+            self.gpu_data.data_mut().data0[0] = self.scale_factor;
+        }
+
+        pub fn scale_factor(&self) -> f32 {
+            self.scale_factor
+        }
+
+        // the size of the target in logical pixels
+        pub fn logical_size(&self) -> (f32, f32) {
+            (self.frame_config.frame_res.0 / self.scale_factor, self.frame_config.frame_res.1 / self.scale_factor)
+        }
+
+        // saves the current transform, to be restored with pop_transform
+        pub fn push_transform(&mut self) {
+            self.frame_config.transform_stack.push(self.frame_config.transform);
+        }
+
+        pub fn pop_transform(&mut self) {
+            match self.frame_config.transform_stack.pop() {
+                Some(transform) => self.frame_config.transform = transform,
+                None => log::warn!("pop_transform called without a matching push_transform"),
+            }
+        }
+
+        // translate, scale and rotate apply to everything drawn afterwards, relative to the current transform.
+        // they work in logical pixels with the origin at the bottom left, like rectp
+        pub fn translate(&mut self, x: f32, y: f32) {
+            let (x, y) = (x * self.scale_factor, y * self.scale_factor);
+            self.frame_config.transform = self.frame_config.transform * cgmath::Matrix3::new(
+                1.0, 0.0, 0.0,
+                0.0, 1.0, 0.0,
+                x, y, 1.0);
+        }
+
+        pub fn scale(&mut self, x: f32, y: f32) {
+            self.frame_config.transform = self.frame_config.transform * cgmath::Matrix3::new(
+                x, 0.0, 0.0,
+                0.0, y, 0.0,
+                0.0, 0.0, 1.0);
+        }
+
+        // counter clockwise, in radians
+        pub fn rotate(&mut self, angle: f32) {
+            let (sin, cos) = angle.sin_cos();
+            self.frame_config.transform = self.frame_config.transform * cgmath::Matrix3::new(
+                cos, sin, 0.0,
+                -sin, cos, 0.0,
+                0.0, 0.0, 1.0);
+        }
+
+        // rotates each item around its own center, counter clockwise in radians, applied before the transform
+        pub fn set_rotation(&mut self, angle: f32) {
+            self.frame_config.rotation = angle;
         }
         
+        // the color also tints the texture, if one is set
         pub fn set_color(&mut self, color_r: f32, color_g: f32, color_b: f32, color_a: f32) {
             self.frame_config.color = (color_r, color_g, color_b, color_a);
-            self.frame_config.texture = None;
+        }
+
+        // the region must come from a packer uploaded with upload_atlas
+        pub fn set_texture(&mut self, region: Option<&crate::TexturePackerRegion>) {
+            self.frame_config.texture = region.cloned();
         }
         
+        // 0 is nearest and 1 farthest, only orders items within the same layer
         pub fn set_depth(&mut self, depth: f32) {
-            self.frame_config.depth = depth;
+            self.frame_config.depth = depth.clamp(0.0, 1.0);
+        }
+
+        // orders items within the current flush, see RendererLayer
+        pub fn set_layer(&mut self, layer: RendererLayer) {
+            self.frame_config.layer = layer;
+        }
+
+        pub fn layer(&self) -> RendererLayer {
+            self.frame_config.layer
         }
         
         pub fn set_mask(&mut self, mask_x: f32, mask_y: f32, mask_w: f32, mask_h: f32) {
             self.frame_config.mask = (mask_x, mask_y, mask_w, mask_h);
         }
         
-        pub fn set_maskp(&mut self, mask_x: i32, mask_y: i32, mask_w: i32, mask_h: i32) {
-            let mask_x = mask_x as f32 / self.frame_config.frame_res.0;
-            let mask_y = mask_y as f32 / self.frame_config.frame_res.1;
-            let mask_w = mask_w as f32 / self.frame_config.frame_res.0;
-            let mask_h = mask_h as f32 / self.frame_config.frame_res.1;
-            
+        pub fn set_maskp(&mut self, mask_x: f32, mask_y: f32, mask_w: f32, mask_h: f32) {
+            let (mask_x, mask_y, mask_w, mask_h) = self.snap_rect(mask_x, mask_y, mask_w, mask_h);
             self.set_mask(mask_x, mask_y, mask_w, mask_h);
         }
 
+        // in logical pixels
         pub fn set_mask_border_radius(&mut self, top_left: f32, top_right: f32, bottom_right: f32, bottom_left: f32) {
-            self.frame_config.border_radius_mask = (top_left, top_right, bottom_right, bottom_left);
+            self.frame_config.border_radius_mask = self.to_physical_radii((top_left, top_right, bottom_right, bottom_left));
+        }
+
+        // intersects the current mask with the rect (in the current transform) until the matching pop_clip.
+        // radii are (top_left, top_right, bottom_right, bottom_left) in logical pixels like set_mask_border_radius
+        pub fn push_clip(&mut self, x: f32, y: f32, w: f32, h: f32, radii: (f32, f32, f32, f32)) {
+            self.frame_config.clip_stack.push((self.frame_config.mask, self.frame_config.border_radius_mask));
+
+            let radii = self.to_physical_radii(radii);
+            let rect = self.transform_rect(x, y, w, h);
+            let (mask, border_radius_mask) = Self::intersect_clip(
+                (self.frame_config.mask, self.frame_config.border_radius_mask),
+                (rect, radii),
+            );
+            self.frame_config.mask = mask;
+            self.frame_config.border_radius_mask = border_radius_mask;
+        }
+
+        pub fn push_clipp(&mut self, x: f32, y: f32, w: f32, h: f32, radii: (f32, f32, f32, f32)) {
+            let (x, y, w, h) = self.snap_rect(x, y, w, h);
+            self.push_clip(x, y, w, h, radii);
+        }
+
+        pub fn pop_clip(&mut self) {
+            match self.frame_config.clip_stack.pop() {
+                Some((mask, border_radius_mask)) => {
+                    self.frame_config.mask = mask;
+                    self.frame_config.border_radius_mask = border_radius_mask;
+                },
+                None => log::warn!("pop_clip called without a matching push_clip"),
+            }
         }
         
+        // in logical pixels, clamped to half the smaller side of each item
         pub fn set_border_radius(&mut self, top_left: f32, top_right: f32, bottom_right: f32, bottom_left: f32) {
-            self.frame_config.border_radius = (top_left, top_right, bottom_right, bottom_left);
+            self.frame_config.border_radius = self.to_physical_radii((top_left, top_right, bottom_right, bottom_left));
         }
         
         pub fn rect(&mut self, x: f32, y: f32, w: f32, h: f32) {
-            let mut item = RendererItem {
-                offset_scale        : [x, y, w, h],
-                texture_transform   : [0.0, 0.0, 0.0, 0.0],
-                mask                : [self.frame_config.mask.0, self.frame_config.mask.1, self.frame_config.mask.2, self.frame_config.mask.3],
-                border_radius       : [self.frame_config.border_radius.0, self.frame_config.border_radius.1, self.frame_config.border_radius.2, self.frame_config.border_radius.3],
-                border_radius_mask  : [self.frame_config.border_radius_mask.0, self.frame_config.border_radius_mask.1, self.frame_config.border_radius_mask.2, self.frame_config.border_radius_mask.3],
-                data0               : [self.frame_config.depth, 0.0, 0.0, 0.0],
-                meta0               : [0.0, 0.0, 0.0, 0.0],
-            };
+            let mut item = self.make_item(x, y, w, h);
             
-            // update color
-            item.texture_transform = [self.frame_config.color.0, self.frame_config.color.1, self.frame_config.color.2, self.frame_config.color.3];
+            // update texture
+            if let Some(region) = self.frame_config.texture.as_ref() {
+                item.texture_transform = [region.uv.0, region.uv.1, region.uv.2, region.uv.3];
+                item.meta0[0] = match region.format {
+                    crate::TexturePackerFormat::R8 => Self::ITEM_TYPE_TEXTURE_COVERAGE,
+                    crate::TexturePackerFormat::Rgba8 => Self::ITEM_TYPE_TEXTURE_RGBA,
+                };
+                item.meta0[1] = region.layer as f32;
+            }
             
             self.items.push(item);
         }
         
-        pub fn rectp(&mut self, x: i32, y: i32, w: i32, h: i32) {
-            let x = x as f32 / self.frame_config.frame_res.0;
-            let y = y as f32 / self.frame_config.frame_res.1;
-            let w = w as f32 / self.frame_config.frame_res.0;
-            let h = h as f32 / self.frame_config.frame_res.1;
-            
+        // edges are snapped to whole physical pixels so they stay crisp
+        pub fn rectp(&mut self, x: f32, y: f32, w: f32, h: f32) {
+            let (x, y, w, h) = self.snap_rect(x, y, w, h);
             self.rect(x, y, w, h)
         }
+
+        // outline of the rect with the current color and border radius, the stroke (in logical pixels) goes inwards
+        pub fn stroke_rect(&mut self, x: f32, y: f32, w: f32, h: f32, stroke_width: f32) {
+            let mut item = self.make_item(x, y, w, h);
+            item.meta0[0] = Self::ITEM_TYPE_STROKE;
+            item.data0[2] = stroke_width * self.scale_factor;
+            self.items.push(item);
+        }
+
+        pub fn stroke_rectp(&mut self, x: f32, y: f32, w: f32, h: f32, stroke_width: f32) {
+            let (x, y, w, h) = self.snap_rect(x, y, w, h);
+            self.stroke_rect(x, y, w, h, stroke_width)
+        }
+
+        // soft shadow of the rect with the current color and border radius, blur and offset are in logical pixels
+        pub fn shadow_rect(&mut self, x: f32, y: f32, w: f32, h: f32, blur: f32, offset: (f32, f32)) {
+            let frame_res = self.frame_config.frame_res;
+            let offset = (offset.0 * self.scale_factor, offset.1 * self.scale_factor);
+            let shape = [(x * frame_res.0) + offset.0, (y * frame_res.1) + offset.1, w * frame_res.0, h * frame_res.1];
+
+            // the quad has to cover the blurred edge as well
+            let blur = blur.max(0.0) * self.scale_factor;
+            let mut item = self.make_item(
+                (shape[0] - blur) / frame_res.0,
+                (shape[1] - blur) / frame_res.1,
+                (shape[2] + blur * 2.0) / frame_res.0,
+                (shape[3] + blur * 2.0) / frame_res.1,
+            );
+            item.meta0[0] = Self::ITEM_TYPE_SHADOW;
+            item.data0[2] = blur;
+            item.data1 = shape;
+            self.items.push(item);
+        }
+
+        pub fn shadow_rectp(&mut self, x: f32, y: f32, w: f32, h: f32, blur: f32, offset: (f32, f32)) {
+            let (x, y, w, h) = self.snap_rect(x, y, w, h);
+            self.shadow_rect(x, y, w, h, blur, offset)
+        }
+
+        // fills the rect with the gradient instead of the current color, border radius and masks still apply
+        pub fn gradient_rect(&mut self, x: f32, y: f32, w: f32, h: f32, gradient: RendererGradient) {
+            let mut item = self.make_item(x, y, w, h);
+            match gradient {
+                RendererGradient::Linear { from, to, start, end } => {
+                    item.meta0[0] = Self::ITEM_TYPE_LINEAR_GRADIENT;
+                    item.color = [from.0, from.1, from.2, from.3];
+                    item.color1 = [to.0, to.1, to.2, to.3];
+                    item.data1 = [start.0, start.1, end.0, end.1];
+                },
+                RendererGradient::Radial { from, to, center, radius } => {
+                    item.meta0[0] = Self::ITEM_TYPE_RADIAL_GRADIENT;
+                    item.color = [from.0, from.1, from.2, from.3];
+                    item.color1 = [to.0, to.1, to.2, to.3];
+                    item.data1 = [center.0, center.1, radius * self.scale_factor, 0.0];
+                },
+            }
+            self.items.push(item);
+        }
+
+        pub fn gradient_rectp(&mut self, x: f32, y: f32, w: f32, h: f32, gradient: RendererGradient) {
+            let (x, y, w, h) = self.snap_rect(x, y, w, h);
+            self.gradient_rect(x, y, w, h, gradient)
+        }
+
+        // filled ellipse inside the rect
+        pub fn ellipse(&mut self, x: f32, y: f32, w: f32, h: f32) {
+            self.stroke_ellipse(x, y, w, h, 0.0);
+        }
+
+        pub fn ellipsep(&mut self, x: f32, y: f32, w: f32, h: f32) {
+            self.stroke_ellipsep(x, y, w, h, 0.0);
+        }
+
+        // outline of the ellipse inside the rect, the stroke (in logical pixels) goes inwards, 0 fills it
+        pub fn stroke_ellipse(&mut self, x: f32, y: f32, w: f32, h: f32, stroke_width: f32) {
+            let mut item = self.make_item(x, y, w, h);
+            item.meta0[0] = Self::ITEM_TYPE_ELLIPSE;
+            item.data0[2] = stroke_width * self.scale_factor;
+            self.items.push(item);
+        }
+
+        // curved edges are left to the anti aliasing, only rects get snapped
+        pub fn stroke_ellipsep(&mut self, x: f32, y: f32, w: f32, h: f32, stroke_width: f32) {
+            let (x, y, w, h) = self.to_normalized(x, y, w, h);
+            self.stroke_ellipse(x, y, w, h, stroke_width);
+        }
+
+        pub fn circlep(&mut self, center_x: f32, center_y: f32, radius: f32) {
+            self.ellipsep(center_x - radius, center_y - radius, radius * 2.0, radius * 2.0);
+        }
+
+        pub fn stroke_circlep(&mut self, center_x: f32, center_y: f32, radius: f32, stroke_width: f32) {
+            self.stroke_ellipsep(center_x - radius, center_y - radius, radius * 2.0, radius * 2.0, stroke_width);
+        }
+
+        // anti aliased line with round caps, everything in logical pixels. the ends are snapped to pixel centers
+        // for odd physical thicknesses and to pixel edges for even ones, so straight lines cover whole pixels
+        pub fn linep(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, thickness: f32) {
+            let frame_res = self.frame_config.frame_res;
+            let thickness = thickness.max(0.0) * self.scale_factor;
+            let snap_offset = if thickness.round() as i32 % 2 == 1 { 0.5 } else { 0.0 };
+            let snap = |v: f32| (v * self.scale_factor - snap_offset).round() + snap_offset;
+            let (x0, y0, x1, y1) = (snap(x0), snap(y0), snap(x1), snap(y1));
+            let half = thickness * 0.5;
+            let (min_x, min_y) = (x0.min(x1) - half, y0.min(y1) - half);
+            let (max_x, max_y) = (x0.max(x1) + half, y0.max(y1) + half);
+
+            let mut item = self.make_item(min_x / frame_res.0, min_y / frame_res.1, (max_x - min_x) / frame_res.0, (max_y - min_y) / frame_res.1);
+            item.meta0[0] = Self::ITEM_TYPE_LINE;
+            item.data0[2] = thickness;
+            item.data1 = [x0, y0, x1, y1];
+            self.items.push(item);
+        }
+
+        // one line per segment, the round caps make up the joints
+        pub fn polylinep(&mut self, points: &[(f32, f32)], thickness: f32) {
+            for segment in points.windows(2) {
+                self.linep(segment[0].0, segment[0].1, segment[1].0, segment[1].1, thickness);
+            }
+        }
+
+        // arc around the center with round caps, angles are in radians counter clockwise from the x axis,
+        // the radius is to the middle of the stroke, everything else in logical pixels
+        pub fn arcp(&mut self, center_x: f32, center_y: f32, radius: f32, start_angle: f32, sweep_angle: f32, thickness: f32) {
+            let frame_res = self.frame_config.frame_res;
+            let (center_x, center_y) = (center_x * self.scale_factor, center_y * self.scale_factor);
+            let radius = radius * self.scale_factor;
+            let thickness = thickness.max(0.0) * self.scale_factor;
+            let extent = radius + thickness * 0.5;
+
+            let mut item = self.make_item((center_x - extent) / frame_res.0, (center_y - extent) / frame_res.1, extent * 2.0 / frame_res.0, extent * 2.0 / frame_res.1);
+            item.meta0[0] = Self::ITEM_TYPE_ARC;
+            item.data0[2] = thickness;
+            item.data1 = [radius, start_angle, sweep_angle.clamp(-std::f32::consts::TAU, std::f32::consts::TAU), 0.0];
+            self.items.push(item);
+        }
+
+        // draws what is behind the rect (everything in lower layers, or earlier in the same one) blurred, with the
+        // current border radius. the current color is mixed over it by its alpha, blur is in logical pixels and
+        // limited to BlurPass::MAX_RADIUS physical pixels
+        pub fn backdrop_rect(&mut self, x: f32, y: f32, w: f32, h: f32, blur: f32) {
+            let mut item = self.make_item(x, y, w, h);
+            item.meta0[0] = Self::ITEM_TYPE_BACKDROP;
+            item.data0[2] = blur.max(0.0) * self.scale_factor;
+            self.items.push(item);
+        }
+
+        pub fn backdrop_rectp(&mut self, x: f32, y: f32, w: f32, h: f32, blur: f32) {
+            let (x, y, w, h) = self.snap_rect(x, y, w, h);
+            self.backdrop_rect(x, y, w, h, blur)
+        }
+
+        // draws text in logical pixels with y being the baseline.
+        // glyph quads start on whole physical pixels to keep the atlas texels sharp
+        pub fn textp(&mut self, glyphs: RendererGlyphs, x: f32, y: f32, size: f32, text: &str) {
+            let RendererGlyphs { font, packer, prefix } = glyphs;
+            let scale = size * self.scale_factor / font.scale();
+            let y = y * self.scale_factor;
+            let mut pen_x = x * self.scale_factor;
+            for c in text.chars() {
+                let glyph = match font.glyphs().get(&c) {
+                    Some(glyph) => glyph,
+                    None => continue,
+                };
+
+                if let Some(region) = packer.region(&format!("{}{}", prefix, c)) {
+                    self.set_texture(Some(&region));
+                    self.rect(
+                        (pen_x + glyph.origin.0 * scale).round() / self.frame_config.frame_res.0,
+                        (y + glyph.origin.1 * scale).round() / self.frame_config.frame_res.1,
+                        region.width as f32 * scale / self.frame_config.frame_res.0,
+                        region.height as f32 * scale / self.frame_config.frame_res.1,
+                    );
+                }
+
+                pen_x += glyph.advance.0 * scale;
+            }
+            self.set_texture(None);
+        }
         
+        pub fn sample_count(&self) -> u32 {
+            self.sample_count
+        }
+
+        // picks up changes to the shader files in debug builds, a broken shader keeps the previous pipeline.
+        // returns whether any pipeline was replaced
+        pub fn reload_shaders(&mut self, instance: &crate::GPUInstance) -> bool {
+            let reloaded = pollster::block_on(self.pipeline.reload_if_changed(instance));
+            self.blur_pass.reload_shaders(instance) || reloaded
+        }
+
+        // (re)creates the multisampled color texture when the frame size changes
+        fn update_msaa_target(&mut self, instance: &crate::GPUInstance) {
+            if self.sample_count <= 1 {
+                return;
+            }
+
+            let size = (self.frame_config.frame_res.0 as u32, self.frame_config.frame_res.1 as u32);
+            if self.msaa_target.as_ref().map(|(_, msaa_size)| *msaa_size == size).unwrap_or(false) {
+                return;
+            }
+
+            let view = Self::create_color_target(instance, self.target_format, size, self.sample_count, "RendererMsaaTarget");
+            self.msaa_target = Some((view, size));
+            self.msaa_holds_target = false;
+        }
+
+        // (re)creates the texture frames with backdrops are drawn into when the frame size changes
+        fn update_scene_target(&mut self, instance: &crate::GPUInstance) {
+            let size = (self.frame_config.frame_res.0 as u32, self.frame_config.frame_res.1 as u32);
+            if self.scene_target.as_ref().map(|(_, scene_size)| *scene_size == size).unwrap_or(false) {
+                return;
+            }
+
+            let view = Self::create_color_target(instance, self.target_format, size, 1, "RendererSceneTarget");
+            self.scene_target = Some((view, size));
+        }
+
+        fn create_color_target(instance: &crate::GPUInstance, format: wgpu::TextureFormat, size: (u32, u32), sample_count: u32, label: &str) -> wgpu::TextureView {
+            let usage = if sample_count > 1 {
+                wgpu::TextureUsages::RENDER_ATTACHMENT
+            } else {
+                wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+            };
+
+            let texture = instance.device().create_texture(&wgpu::TextureDescriptor {
+                label               : Some(label),
+                size                : wgpu::Extent3d {
+                                        width                   : size.0.max(1),
+                                        height                  : size.1.max(1),
+                                        depth_or_array_layers   : 1,
+                                    },
+                mip_level_count     : 1,
+                sample_count        : sample_count,
+                dimension           : wgpu::TextureDimension::D2,
+                format              : format,
+                usage               : usage,
+                view_formats        : &[],
+            });
+            texture.create_view(&wgpu::TextureViewDescriptor::default())
+        }
+
+        // logical pixels to normalized coordinates
+        fn to_normalized(&self, x: f32, y: f32, w: f32, h: f32) -> (f32, f32, f32, f32) {
+            let frame_res = self.frame_config.frame_res;
+            let scale = self.scale_factor;
+            (x * scale / frame_res.0, y * scale / frame_res.1, w * scale / frame_res.0, h * scale / frame_res.1)
+        }
+
+        // logical pixels to normalized coordinates with the edges rounded to whole physical pixels
+        fn snap_rect(&self, x: f32, y: f32, w: f32, h: f32) -> (f32, f32, f32, f32) {
+            let frame_res = self.frame_config.frame_res;
+            let scale = self.scale_factor;
+            let (x0, y0) = ((x * scale).round(), (y * scale).round());
+            let (x1, y1) = (((x + w) * scale).round(), ((y + h) * scale).round());
+            (x0 / frame_res.0, y0 / frame_res.1, (x1 - x0) / frame_res.0, (y1 - y0) / frame_res.1)
+        }
+
+        fn to_physical_radii(&self, radii: (f32, f32, f32, f32)) -> (f32, f32, f32, f32) {
+            let scale = self.scale_factor;
+            (radii.0 * scale, radii.1 * scale, radii.2 * scale, radii.3 * scale)
+        }
+
+        // a solid color item with the current frame config applied
+        fn make_item(&self, x: f32, y: f32, w: f32, h: f32) -> RendererItem {
+            let transform = &self.frame_config.transform;
+            RendererItem {
+                offset_scale        : [x, y, w, h],
+                texture_transform   : [0.0, 0.0, 0.0, 0.0],
+                color               : [self.frame_config.color.0, self.frame_config.color.1, self.frame_config.color.2, self.frame_config.color.3],
+                mask                : [self.frame_config.mask.0, self.frame_config.mask.1, self.frame_config.mask.2, self.frame_config.mask.3],
+                border_radius       : [self.frame_config.border_radius.0, self.frame_config.border_radius.1, self.frame_config.border_radius.2, self.frame_config.border_radius.3],
+                border_radius_mask  : [self.frame_config.border_radius_mask.0, self.frame_config.border_radius_mask.1, self.frame_config.border_radius_mask.2, self.frame_config.border_radius_mask.3],
+                data0               : [self.frame_config.depth, self.frame_config.rotation, 0.0, 0.0],
+                data1               : [0.0, 0.0, 0.0, 0.0],
+                color1              : [0.0, 0.0, 0.0, 0.0],
+                meta0               : [Self::ITEM_TYPE_COLOR, 0.0, self.frame_config.layer as u32 as f32, 0.0],
+                transform0          : [transform.x.x, transform.x.y, transform.y.x, transform.y.y],
+                transform1          : [transform.z.x, transform.z.y, 0.0, 0.0],
+            }
+        }
+
+        // physical pixels (x, y, width, height) an item can cover in the target, y down. follows vs_main: the quad grown
+        // by a pixel for the anti aliased edge, rotated around its center, then transformed
+        fn item_bounds(&self, item: &RendererItem) -> (u32, u32, u32, u32) {
+            let frame_res = self.frame_config.frame_res;
+            let (x, y) = (item.offset_scale[0] * frame_res.0, item.offset_scale[1] * frame_res.1);
+            let (w, h) = (item.offset_scale[2] * frame_res.0, item.offset_scale[3] * frame_res.1);
+            let center = (x + w * 0.5, y + h * 0.5);
+            let (sin, cos) = item.data0[1].sin_cos();
+            let corners = [(x - 1.0, y - 1.0), (x + w + 1.0, y - 1.0), (x + w + 1.0, y + h + 1.0), (x - 1.0, y + h + 1.0)].map(|(cx, cy)| {
+                let (dx, dy) = (cx - center.0, cy - center.1);
+                let (px, py) = (center.0 + cos * dx - sin * dy, center.1 + sin * dx + cos * dy);
+                (item.transform0[0] * px + item.transform0[2] * py + item.transform1[0],
+                 item.transform0[1] * px + item.transform0[3] * py + item.transform1[1])
+            });
+
+            let min_x = corners.iter().map(|c| c.0).fold(f32::MAX, f32::min).floor().clamp(0.0, frame_res.0);
+            let max_x = corners.iter().map(|c| c.0).fold(f32::MIN, f32::max).ceil().clamp(0.0, frame_res.0);
+            let min_y = corners.iter().map(|c| c.1).fold(f32::MAX, f32::min).floor().clamp(0.0, frame_res.1);
+            let max_y = corners.iter().map(|c| c.1).fold(f32::MIN, f32::max).ceil().clamp(0.0, frame_res.1);
+            (min_x as u32, (frame_res.1 - max_y) as u32, (max_x - min_x) as u32, (max_y - min_y) as u32)
+        }
+
+        // whether b, grown by the margin, touches a
+        fn regions_overlap(a: (u32, u32, u32, u32), b: (u32, u32, u32, u32), margin: u32) -> bool {
+            a.0 < b.0 + b.2 + margin && b.0 < a.0 + a.2 + margin
+                && a.1 < b.1 + b.3 + margin && b.1 < a.1 + a.3 + margin
+        }
+
+        // bounding box of the transformed rect, masks are axis aligned so rotations only clip approximately
+        fn transform_rect(&self, x: f32, y: f32, w: f32, h: f32) -> (f32, f32, f32, f32) {
+            let frame_res = self.frame_config.frame_res;
+            let transform = &self.frame_config.transform;
+            let corners = [(x, y), (x + w, y), (x + w, y + h), (x, y + h)].map(|(cx, cy)| {
+                let point = transform * cgmath::Vector3::new(cx * frame_res.0, cy * frame_res.1, 1.0);
+                (point.x / frame_res.0, point.y / frame_res.1)
+            });
+
+            let min_x = corners.iter().map(|c| c.0).fold(f32::MAX, f32::min);
+            let min_y = corners.iter().map(|c| c.1).fold(f32::MAX, f32::min);
+            let max_x = corners.iter().map(|c| c.0).fold(f32::MIN, f32::max);
+            let max_y = corners.iter().map(|c| c.1).fold(f32::MIN, f32::max);
+            (min_x, min_y, max_x - min_x, max_y - min_y)
+        }
+
+        // a corner of the intersection keeps the radius of every clip it is also a corner of,
+        // which is exact for the usual case of a clip nested inside (or aligned with) its parent
+        fn intersect_clip(parent: ((f32, f32, f32, f32), (f32, f32, f32, f32)), child: ((f32, f32, f32, f32), (f32, f32, f32, f32))) -> ((f32, f32, f32, f32), (f32, f32, f32, f32)) {
+            let ((px, py, pw, ph), parent_radii) = parent;
+            let ((cx, cy, cw, ch), child_radii) = child;
+
+            let x0 = px.max(cx);
+            let y0 = py.max(cy);
+            let x1 = (px + pw).min(cx + cw);
+            let y1 = (py + ph).min(cy + ch);
+            if x1 <= x0 || y1 <= y0 {
+                return ((x0, y0, 0.0, 0.0), (0.0, 0.0, 0.0, 0.0));
+            }
+
+            // (top_left, top_right, bottom_right, bottom_left), the origin is at the bottom left
+            let corners = |x0: f32, y0: f32, x1: f32, y1: f32| [(x0, y1), (x1, y1), (x1, y0), (x0, y0)];
+            let radii = |r: (f32, f32, f32, f32)| [r.0, r.1, r.2, r.3];
+            let clips = [
+                (corners(px, py, px + pw, py + ph), radii(parent_radii)),
+                (corners(cx, cy, cx + cw, cy + ch), radii(child_radii)),
+            ];
+
+            let mut result = [0.0f32; 4];
+            for (index, corner) in corners(x0, y0, x1, y1).iter().enumerate() {
+                for (clip_corners, clip_radii) in clips.iter() {
+                    let other = clip_corners[index];
+                    if (other.0 - corner.0).abs() < f32::EPSILON && (other.1 - corner.1).abs() < f32::EPSILON {
+                        result[index] = result[index].max(clip_radii[index]);
+                    }
+                }
+            }
+
+            ((x0, y0, x1 - x0, y1 - y0), (result[0], result[1], result[2], result[3]))
+        }
+
+        fn create_backdrop_bind_group(instance: &crate::GPUInstance, layout: &wgpu::BindGroupLayout, view: &wgpu::TextureView, sampler: &wgpu::Sampler) -> wgpu::BindGroup {
+            instance.device().create_bind_group(&wgpu::BindGroupDescriptor {
+                label       : Some("RendererBackdrop"),
+                layout      : layout,
+                entries     : &[
+                    wgpu::BindGroupEntry {
+                        binding     : 0,
+                        resource    : wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding     : 1,
+                        resource    : wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+            })
+        }
+
+        fn create_atlas_bind_group(instance: &crate::GPUInstance, layout: &wgpu::BindGroupLayout, coverage: &crate::TextureArray, color: &crate::TextureArray, sampler: &wgpu::Sampler) -> wgpu::BindGroup {
+            instance.device().create_bind_group(&wgpu::BindGroupDescriptor {
+                label       : Some("RendererAtlas"),
+                layout      : layout,
+                entries     : &[
+                    wgpu::BindGroupEntry {
+                        binding     : 0,
+                        resource    : wgpu::BindingResource::TextureView(coverage.view()),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding     : 1,
+                        resource    : wgpu::BindingResource::TextureView(color.view()),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding     : 2,
+                        resource    : wgpu::BindingResource::Sampler(sampler),
+                    },
+                ],
+            })
+        }
         
-    }
\ No newline at end of file
+    }
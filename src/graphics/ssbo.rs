@@ -1,28 +1,52 @@
-pub struct ShaderStorageBuffer {
+// a storage buffer holding an array of T. it grows (never shrinks) when uploads do not fit, which replaces the
+// buffer and the bind group, so those should be fetched again after uploading. the layout stays the same.
+// byte offsets and sizes of uploads have to be multiples of 4, which holds for the usual vec4 based structs
+pub struct ShaderStorageBuffer<T>
+    where T: Copy + Clone + bytemuck::Pod + bytemuck::Zeroable
+{
     buffer              : wgpu::Buffer,
     layout              : wgpu::BindGroupLayout,
     bind_group          : wgpu::BindGroup,
-    size                : usize,
+    len                 : usize,
+    capacity            : usize,
+    read_only           : bool,
+    label               : Option<String>,
+    _phantom            : std::marker::PhantomData<T>,
 }
 
-impl ShaderStorageBuffer {
+impl<T> ShaderStorageBuffer<T>
+    where T: Copy + Clone + bytemuck::Pod + bytemuck::Zeroable
+{
 
-    pub async fn new(instance: &crate::GPUInstance, size: usize, label: Option<&str>) -> Result<Self, String> {
-        let buffer = instance.device().create_buffer(&wgpu::BufferDescriptor {
-            label               : label,
-            size                : size as u64,
-            usage               : wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation  : false,
-        });
+    // read only in shaders, usable from every stage. capacity is in elements
+    pub async fn new(instance: &crate::GPUInstance, capacity: usize, label: Option<&str>) -> Result<Self, String> {
+        Self::create(instance, capacity, true, label)
+    }
+
+    // writable from compute and fragment shaders, vertex shaders can not bind writable storage
+    pub async fn new_read_write(instance: &crate::GPUInstance, capacity: usize, label: Option<&str>) -> Result<Self, String> {
+        Self::create(instance, capacity, false, label)
+    }
+
+    fn create(instance: &crate::GPUInstance, capacity: usize, read_only: bool, label: Option<&str>) -> Result<Self, String> {
+        if std::mem::size_of::<T>() == 0 {
+            return Err("Storage buffer elements can not be zero sized".to_string());
+        }
+
+        let visibility = if read_only {
+            wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT
+        } else {
+            wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT
+        };
 
         let layout = instance.device().create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: label,
             entries: &[
                 wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer { 
-                        ty                  : wgpu::BufferBindingType::Storage { read_only: true },
+                    visibility: visibility,
+                    ty: wgpu::BindingType::Buffer {
+                        ty                  : wgpu::BufferBindingType::Storage { read_only: read_only },
                         has_dynamic_offset  : false,
                         min_binding_size    : None
                     },
@@ -31,22 +55,19 @@ impl ShaderStorageBuffer {
             ],
         });
 
-        let bind_group = instance.device().create_bind_group(&wgpu::BindGroupDescriptor {
-            label: label,
-            layout: &layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: buffer.as_entire_binding(),
-                }
-            ],
-        });
+        // empty bindings are not allowed, so there is always room for at least one element
+        let capacity = capacity.max(1);
+        let (buffer, bind_group) = Self::create_buffer(instance, &layout, capacity, label)?;
 
         Ok(Self {
             buffer          : buffer,
             layout          : layout,
             bind_group      : bind_group,
-            size            : size,
+            len             : 0,
+            capacity        : capacity,
+            read_only       : read_only,
+            label           : label.map(|label| label.to_string()),
+            _phantom        : std::marker::PhantomData,
         })
     }
 
@@ -62,14 +83,137 @@ impl ShaderStorageBuffer {
         &self.bind_group
     }
 
+    // elements uploaded so far
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // elements that fit without reallocating
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // in bytes
     pub fn size(&self) -> usize {
-        self.size
+        self.capacity * std::mem::size_of::<T>()
     }
 
-    pub fn upload(&self, instance: &crate::GPUInstance, data: &[u8]) -> Result<(), String> {
-        instance.queue().write_buffer(&self.buffer, 0, data);
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    // replaces the contents, len becomes data.len()
+    pub fn upload(&mut self, instance: &crate::GPUInstance, data: &[T]) -> Result<(), String> {
+        self.len = 0;
+        self.upload_range(instance, 0, data)
+    }
+
+    // writes data starting at element offset, which can not be past len. len grows to cover the range
+    pub fn upload_range(&mut self, instance: &crate::GPUInstance, offset: usize, data: &[T]) -> Result<(), String> {
+        if offset > self.len {
+            return Err(format!("Upload offset {} is past the end of the buffer ({} elements)", offset, self.len));
+        }
+
+        let byte_offset = offset * std::mem::size_of::<T>();
+        let bytes: &[u8] = bytemuck::cast_slice(data);
+        if !(byte_offset as u64).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) || !(bytes.len() as u64).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT) {
+            return Err(format!("Upload of {} bytes at {} is not {} byte aligned", bytes.len(), byte_offset, wgpu::COPY_BUFFER_ALIGNMENT));
+        }
+
+        self.reserve(instance, offset + data.len())?;
+        if !bytes.is_empty() {
+            instance.queue().write_buffer(&self.buffer, byte_offset as u64, bytes);
+        }
+        self.len = self.len.max(offset + data.len());
         Ok(())
     }
 
+    // grows the buffer to hold at least capacity elements, keeping the current contents
+    pub fn reserve(&mut self, instance: &crate::GPUInstance, capacity: usize) -> Result<(), String> {
+        if capacity <= self.capacity {
+            return Ok(());
+        }
+
+        let capacity = capacity.next_power_of_two();
+        let (buffer, bind_group) = Self::create_buffer(instance, &self.layout, capacity, self.label.as_deref())?;
+
+        // pending writes to the old buffer are ordered before this copy by the queue
+        let copy_size = (self.len * std::mem::size_of::<T>()) as u64;
+        if copy_size > 0 {
+            let mut encoder = instance.encoder("ShaderStorageBuffer::reserve.encoder");
+            encoder.copy_buffer_to_buffer(&self.buffer, 0, &buffer, 0, copy_size);
+            instance.submit(encoder);
+        }
 
-}
\ No newline at end of file
+        log::debug!("Storage buffer {} grown to {} elements", self.label.as_deref().unwrap_or(""), capacity);
+        self.buffer = buffer;
+        self.bind_group = bind_group;
+        self.capacity = capacity;
+        Ok(())
+    }
+
+    // copies the first len elements back once the gpu is done with everything submitted so far
+    pub async fn read_back(&self, instance: &crate::GPUInstance) -> Result<Vec<T>, String> {
+        let size = (self.len * std::mem::size_of::<T>()) as u64;
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+
+        let staging = instance.device().create_buffer(&wgpu::BufferDescriptor {
+            label               : Some("ShaderStorageBuffer::read_back.buffer"),
+            size                : size,
+            usage               : wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation  : false,
+        });
+
+        let mut encoder = instance.encoder("ShaderStorageBuffer::read_back.encoder");
+        encoder.copy_buffer_to_buffer(&self.buffer, 0, &staging, 0, size);
+        instance.submit(encoder);
+
+        instance.map_read(&staging).await?;
+
+        // the mapped range is not necessarily aligned for T, so it is copied out byte wise
+        let data = {
+            let mapped = staging.slice(..).get_mapped_range();
+            let mut data = vec![T::zeroed(); self.len];
+            bytemuck::cast_slice_mut::<T, u8>(&mut data).copy_from_slice(&mapped);
+            data
+        };
+        staging.unmap();
+
+        Ok(data)
+    }
+
+    fn create_buffer(instance: &crate::GPUInstance, layout: &wgpu::BindGroupLayout, capacity: usize, label: Option<&str>) -> Result<(wgpu::Buffer, wgpu::BindGroup), String> {
+        let size = ((capacity * std::mem::size_of::<T>()) as u64).div_ceil(wgpu::COPY_BUFFER_ALIGNMENT) * wgpu::COPY_BUFFER_ALIGNMENT;
+        let max_size = instance.device().limits().max_storage_buffer_binding_size as u64;
+        if size > max_size {
+            return Err(format!("Storage buffer of {} bytes exceeds the limit of {} bytes", size, max_size));
+        }
+
+        let buffer = instance.device().create_buffer(&wgpu::BufferDescriptor {
+            label               : label,
+            size                : size,
+            usage               : wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation  : false,
+        });
+
+        let bind_group = instance.device().create_bind_group(&wgpu::BindGroupDescriptor {
+            label: label,
+            layout: layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }
+            ],
+        });
+
+        Ok((buffer, bind_group))
+    }
+
+}
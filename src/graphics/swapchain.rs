@@ -1,21 +1,52 @@
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum SwapchainPresentMode {
+    #[default]
+    Auto,       // mailbox when available, vsync otherwise
+    Vsync,
+    Mailbox,
+    Immediate,
+}
+
+impl SwapchainPresentMode {
+
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "auto"      => Ok(Self::Auto),
+            "vsync"     => Ok(Self::Vsync),
+            "mailbox"   => Ok(Self::Mailbox),
+            "immediate" => Ok(Self::Immediate),
+            _ => Err(format!("Unknown present mode: {}, expected auto, vsync, mailbox or immediate", name)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Auto      => "auto",
+            Self::Vsync     => "vsync",
+            Self::Mailbox   => "mailbox",
+            Self::Immediate => "immediate",
+        }
+    }
+
+}
+
 pub struct Swapchain {
     surface_config          : wgpu::SurfaceConfiguration,
-    min_size                : (u32, u32),
     max_size                : (u32, u32),
 }
 
 
 impl Swapchain {
 
-    pub async fn new(instance: &crate::GPUInstance) -> Result<Self, String> {
+    pub async fn new(instance: &crate::GPUInstance, present_mode: SwapchainPresentMode) -> Result<Self, String> {
 
         let adapter = instance.adapter();
-        let surface = instance.surface();
+        let surface = instance.surface().ok_or("Cannot create a swapchain without a surface!")?;
 
         let surface_caps = surface.get_capabilities(adapter);
-        let (prefered_format, prefered_present_mode) = Self::get_prefered_format_and_present_mode(&surface_caps);
+        let (prefered_format, prefered_present_mode) = Self::get_prefered_format_and_present_mode(&surface_caps, present_mode);
+        let alpha_mode = Self::get_prefered_alpha_mode(&surface_caps);
 
-        let min_size = (800, 600);
         let max_size = (8000, 8000);
 
         let surface_config = wgpu::SurfaceConfiguration {
@@ -24,7 +55,7 @@ impl Swapchain {
             width           : 800,
             height          : 600,
             present_mode    : prefered_present_mode,
-            alpha_mode      : surface_caps.alpha_modes[0],
+            alpha_mode      : alpha_mode,
             view_formats    : vec![],
         };
 
@@ -33,14 +64,18 @@ impl Swapchain {
 
         Ok(Self {
             surface_config      : surface_config,
-            min_size            : min_size,
             max_size            : max_size,
         })
     }
 
+    // a minimized window resizes to zero, the surface can not be configured or rendered to until it grows again
     pub fn resize(&mut self, width: u32, height: u32) {
-        self.surface_config.width = width.clamp(self.min_size.0, self.max_size.0);
-        self.surface_config.height = height.clamp(self.min_size.1, self.max_size.1);
+        self.surface_config.width = width.min(self.max_size.0);
+        self.surface_config.height = height.min(self.max_size.1);
+    }
+
+    pub fn is_zero_sized(&self) -> bool {
+        self.surface_config.width == 0 || self.surface_config.height == 0
     }
 
     pub fn surface_config(&self) -> &wgpu::SurfaceConfiguration {
@@ -48,7 +83,7 @@ impl Swapchain {
     }
 
     pub fn acquire_texture(&self, instance: &crate::GPUInstance) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
-        Ok(instance.surface().get_current_texture()?)
+        Ok(instance.surface().ok_or(wgpu::SurfaceError::Lost)?.get_current_texture()?)
     }
 
     pub fn present(&self, surface_texture: wgpu::SurfaceTexture) -> Result<(), String> {
@@ -57,7 +92,8 @@ impl Swapchain {
     }
 
 
-    fn get_prefered_format_and_present_mode(surface_caps: &wgpu::SurfaceCapabilities) -> (wgpu::TextureFormat, wgpu::PresentMode) {
+    // fifo is always supported, so it is what an unsupported present mode falls back to
+    fn get_prefered_format_and_present_mode(surface_caps: &wgpu::SurfaceCapabilities, present_mode: SwapchainPresentMode) -> (wgpu::TextureFormat, wgpu::PresentMode) {
         let formats = surface_caps.formats.clone();
         let present_modes = surface_caps.present_modes.clone();
 
@@ -65,11 +101,45 @@ impl Swapchain {
             format.is_srgb()
         }).map(|&format| format).unwrap_or(formats[0]);
 
-        let present_mode = present_modes.iter().find(|&mode| {
-            *mode == wgpu::PresentMode::Mailbox
-        }).map(|&mode| mode).unwrap_or(present_modes[0]);
+        let requested = match present_mode {
+            SwapchainPresentMode::Auto | SwapchainPresentMode::Mailbox  => wgpu::PresentMode::Mailbox,
+            SwapchainPresentMode::Vsync                                 => wgpu::PresentMode::Fifo,
+            SwapchainPresentMode::Immediate                             => wgpu::PresentMode::Immediate,
+        };
+
+        let present_mode = if present_modes.contains(&requested) {
+            requested
+        } else {
+            if present_mode != SwapchainPresentMode::Auto {
+                log::warn!("Present mode {:?} is not supported ({:?}), using vsync", requested, present_modes);
+            }
+            wgpu::PresentMode::Fifo
+        };
+        log::info!("Present mode: {:?}", present_mode);
 
         (format, present_mode)
     }
 
+    // the renderer blends onto a transparent clear color, which leaves premultiplied colors in the target.
+    // opaque is the last resort and loses the window transparency
+    fn get_prefered_alpha_mode(surface_caps: &wgpu::SurfaceCapabilities) -> wgpu::CompositeAlphaMode {
+        let prefered = [
+            wgpu::CompositeAlphaMode::PreMultiplied,
+            wgpu::CompositeAlphaMode::PostMultiplied,
+            wgpu::CompositeAlphaMode::Inherit,
+        ];
+
+        let alpha_mode = prefered.iter()
+            .find(|mode| surface_caps.alpha_modes.contains(mode))
+            .map(|&mode| mode)
+            .unwrap_or(surface_caps.alpha_modes[0]);
+
+        if alpha_mode == wgpu::CompositeAlphaMode::Opaque {
+            log::warn!("The surface only supports opaque alpha modes ({:?}), the window will not be transparent", surface_caps.alpha_modes);
+        } else {
+            log::info!("Surface alpha mode: {:?}", alpha_mode);
+        }
+        alpha_mode
+    }
+
 }
\ No newline at end of file
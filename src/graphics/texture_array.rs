@@ -0,0 +1,114 @@
+pub struct TextureArray {
+    texture         : wgpu::Texture,
+    view            : wgpu::TextureView,
+    format          : wgpu::TextureFormat,
+    width           : u32,
+    height          : u32,
+    layer_count     : u32,
+    mip_level_count : u32,
+}
+
+impl TextureArray {
+
+    pub async fn new(instance: &crate::GPUInstance, width: u32, height: u32, layer_count: u32, mip_level_count: u32, format: wgpu::TextureFormat, label: Option<&str>) -> Result<Self, String> {
+        if width == 0 || height == 0 || layer_count == 0 {
+            return Err(format!("Invalid texture array size: {}x{}x{}", width, height, layer_count));
+        }
+
+        if mip_level_count == 0 || mip_level_count > 32 - width.max(height).leading_zeros() {
+            return Err(format!("Invalid mip level count {} for size {}x{}", mip_level_count, width, height));
+        }
+
+        let texture = instance.device().create_texture(&wgpu::TextureDescriptor {
+            label               : label,
+            size                : wgpu::Extent3d {
+                                    width                   : width,
+                                    height                  : height,
+                                    depth_or_array_layers   : layer_count,
+                                },
+            mip_level_count     : mip_level_count,
+            sample_count        : 1,
+            dimension           : wgpu::TextureDimension::D2,
+            format              : format,
+            usage               : wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats        : &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label               : label,
+            dimension           : Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        Ok(Self {
+            texture         : texture,
+            view            : view,
+            format          : format,
+            width           : width,
+            height          : height,
+            layer_count     : layer_count,
+            mip_level_count : mip_level_count,
+        })
+    }
+
+    pub fn upload_layer(&self, instance: &crate::GPUInstance, layer: u32, level: u32, data: &[u8]) -> Result<(), String> {
+        if layer >= self.layer_count {
+            return Err(format!("Layer {} out of range, texture array has {} layers", layer, self.layer_count));
+        }
+
+        if level >= self.mip_level_count {
+            return Err(format!("Mip level {} out of range, texture array has {} levels", level, self.mip_level_count));
+        }
+
+        let width = (self.width >> level).max(1);
+        let height = (self.height >> level).max(1);
+        let bytes_per_pixel = self.format.block_size(None).ok_or("Unsupported texture format")?;
+        let expected_size = (width * height * bytes_per_pixel) as usize;
+        if data.len() != expected_size {
+            return Err(format!("Layer data has size {}, expected {}", data.len(), expected_size));
+        }
+
+        instance.queue().write_texture(
+            wgpu::ImageCopyTexture {
+                texture     : &self.texture,
+                mip_level   : level,
+                origin      : wgpu::Origin3d { x: 0, y: 0, z: layer },
+                aspect      : wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::ImageDataLayout {
+                offset          : 0,
+                bytes_per_row   : Some(width * bytes_per_pixel),
+                rows_per_image  : Some(height),
+            },
+            wgpu::Extent3d {
+                width                   : width,
+                height                  : height,
+                depth_or_array_layers   : 1,
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    pub fn size(&self) -> (u32, u32, u32) {
+        (self.width, self.height, self.layer_count)
+    }
+
+    pub fn mip_level_count(&self) -> u32 {
+        self.mip_level_count
+    }
+
+}
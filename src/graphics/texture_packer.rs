@@ -1,8 +1,38 @@
 use core::str;
 use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
 
 use rayon::prelude::*;
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TexturePackerFormat 
+{
+    R8,         // single channel coverage, used for glyphs
+    Rgba8,
+}
+
+// used by update when the data size differs from the reserved space
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TexturePackerFilter 
+{
+    Nearest,
+    Bilinear,
+    Lanczos3,
+}
+
+// public view of a registered space
+#[derive(Copy, Clone, Debug)]
+pub struct TexturePackerRegion 
+{
+    pub x           : u32,
+    pub y           : u32,
+    pub width       : u32,
+    pub height      : u32,
+    pub layer       : u32,
+    pub uv          : (f32, f32, f32, f32),    // (x, y), (scale_x, scale_y) normalized to the layer size
+    pub format      : TexturePackerFormat,
+}
+
 struct TexturePackerSpace 
 {
     x               : u32,
@@ -17,12 +47,91 @@ pub struct TexturePacker
     width           : u32,
     height          : u32,
     depth           : u32,
+    format          : TexturePackerFormat,
+    filter          : TexturePackerFilter,
     layers          : Vec<Vec<u8>>,
+    mipmaps         : Vec<Vec<Vec<u8>>>,    // per layer, level 1 onwards
+
     spaces          : Vec<TexturePackerSpace>,
     registered      : std::collections::HashMap<String, TexturePackerSpace>,
     to_add          : Vec<(String, u32, u32)>,
 }
 
+impl TexturePackerFormat 
+{
+    pub fn bytes_per_pixel(&self) -> u32 
+    {
+        match self {
+            Self::R8 => 1,
+            Self::Rgba8 => 4,
+        }
+    }
+    
+    pub fn color_type(&self) -> image::ColorType 
+    {
+        match self {
+            Self::R8 => image::ColorType::L8,
+            Self::Rgba8 => image::ColorType::Rgba8,
+        }
+    }
+    
+    pub fn texture_format(&self) -> wgpu::TextureFormat 
+    {
+        match self {
+            Self::R8 => wgpu::TextureFormat::R8Unorm,
+            Self::Rgba8 => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+    
+    pub fn name(&self) -> &'static str 
+    {
+        match self {
+            Self::R8 => "r8",
+            Self::Rgba8 => "rgba8",
+        }
+    }
+    
+    pub fn from_name(name: &str) -> Result<Self, String> 
+    {
+        match name {
+            "r8" => Ok(Self::R8),
+            "rgba8" => Ok(Self::Rgba8),
+            _ => Err(format!("Unknown texture packer format: {}", name)),
+        }
+    }
+}
+
+impl TexturePackerFilter 
+{
+    pub fn filter_type(&self) -> image::imageops::FilterType 
+    {
+        match self {
+            Self::Nearest => image::imageops::FilterType::Nearest,
+            Self::Bilinear => image::imageops::FilterType::Triangle,
+            Self::Lanczos3 => image::imageops::FilterType::Lanczos3,
+        }
+    }
+    
+    pub fn name(&self) -> &'static str 
+    {
+        match self {
+            Self::Nearest => "nearest",
+            Self::Bilinear => "bilinear",
+            Self::Lanczos3 => "lanczos3",
+        }
+    }
+    
+    pub fn from_name(name: &str) -> Result<Self, String> 
+    {
+        match name {
+            "nearest" => Ok(Self::Nearest),
+            "bilinear" => Ok(Self::Bilinear),
+            "lanczos3" => Ok(Self::Lanczos3),
+            _ => Err(format!("Unknown texture packer filter: {}", name)),
+        }
+    }
+}
+
 impl TexturePackerSpace 
 {
     pub fn area(&self) -> u32 
@@ -44,17 +153,46 @@ impl TexturePackerSpace
     {
         self.area().cmp(&other.area())
     }
+    
+    // stored as [x, y, width, height, layer]
+    pub fn to_yaml(&self) -> serde_yaml::Value 
+    {
+        serde_yaml::Value::Sequence(vec![self.x.into(), self.y.into(), self.width.into(), self.height.into(), self.layer.into()])
+    }
+    
+    pub fn from_yaml(value: &serde_yaml::Value) -> Result<Self, String> 
+    {
+        let values = value.as_sequence().ok_or("Space is not a sequence")?
+            .iter()
+            .map(|v| v.as_u64().and_then(|v| u32::try_from(v).ok()).ok_or("Space contains a non integer or out of range value"))
+            .collect::<Result<Vec<u32>, &str>>()?;
+        
+        if values.len() != 5 {
+            return Err(format!("Space has {} values, expected 5", values.len()));
+        }
+        
+        Ok(Self {
+            x               : values[0],
+            y               : values[1],
+            width           : values[2],
+            height          : values[3],
+            layer           : values[4],
+        })
+    }
 }
 
 impl TexturePacker 
 {
+
+    // bump this whenever the on-disk cache layout changes
+    const CACHE_VERSION: u32 = 2;
     
-    pub async fn new(width: u32, height: u32, layer_count: u32) -> Result<Self, String> 
+    pub async fn new(width: u32, height: u32, layer_count: u32, format: TexturePackerFormat) -> Result<Self, String> 
     {
         let mut layers = Vec::new();
         let mut spaces = Vec::new();
         for i in 0..layer_count {
-            layers.push(vec![0u8; (width * height * 4) as usize]);
+            layers.push(vec![0u8; (width * height * format.bytes_per_pixel()) as usize]);
             spaces.push(TexturePackerSpace {
                 x               : 0,
                 y               : 0,
@@ -68,7 +206,10 @@ impl TexturePacker
             width           : width,
             height          : height,
             depth           : layers.len() as u32,
+            format          : format,
+            filter          : TexturePackerFilter::Bilinear,
             layers          : layers,
+            mipmaps         : Vec::new(),
             spaces          : spaces,
             registered      : std::collections::HashMap::new(),
             to_add          : Vec::new(),
@@ -77,8 +218,8 @@ impl TexturePacker
     
     pub fn save(&self, path: &str, layer: u32) -> Result<(), String> 
     {
-        assert!(layer < self.depth);
-        image::save_buffer(path, &self.layers[layer as usize], self.width, self.height, image::ColorType::Rgba8).map_err(|e| e.to_string())
+        let data = self.layer_data(layer).ok_or(format!("Layer {} out of range, packer has {} layers", layer, self.depth))?;
+        image::save_buffer(path, data, self.width, self.height, self.format.color_type()).map_err(|e| format!("Failed to save layer {} to {}: {}", layer, path, e))
     }
     
     pub fn reset(&mut self) 
@@ -95,43 +236,148 @@ impl TexturePacker
         }
         self.registered.clear();
         self.to_add.clear();
+        self.mipmaps.clear();
     }
     
     
+    // adding a name that is already registered replaces its region on the next pack
     pub fn add(&mut self, name: &str, width: u32, height: u32) {
         self.to_add.push((name.to_string(), width, height));
     }
     
-    pub fn update(&mut self, name: &str, data: &[u8], width: u32, height: u32) {
-        if let Some(space) = self.registered.get(name) {
-            let layer = &mut self.layers[space.layer as usize];
-            // scale the data to the space
-            for y in 0..space.height {
-                for x in 0..space.width {
-                    
-                    if width != space.width || height != space.height {
-                        let x_f = x as f32 / space.width as f32;
-                        let y_f = y as f32 / space.height as f32;
-                        let xi = (x_f * (width - 1) as f32) as u32;
-                        let yi = (y_f * (height - 1) as f32) as u32;
-                        let indexi = (yi * width + xi) as usize * 4;
-                        let indexo = ((space.y + y) * self.width + (space.x + x)) as usize * 4;
-                        layer[indexo + 0] = data[indexi + 0];
-                        layer[indexo + 1] = data[indexi + 1];
-                        layer[indexo + 2] = data[indexi + 2];
-                        layer[indexo + 3] = data[indexi + 3];
-                    }
-                    else {
-                        let indexi = (y * width + x) as usize * 4;
-                        let indexo = ((space.y + y) * self.width + (space.x + x)) as usize * 4;
-                        layer[indexo + 0] = data[indexi + 0];
-                        layer[indexo + 1] = data[indexi + 1];
-                        layer[indexo + 2] = data[indexi + 2];
-                        layer[indexo + 3] = data[indexi + 3];
-                    }
-                }
+    // gives the space of a registered region back to the packer, or drops a pending add
+    pub fn remove(&mut self, name: &str) -> Result<(), String> 
+    {
+        if let Some(space) = self.registered.remove(name) {
+            self.spaces.push(space);
+            return Ok(());
+        }
+        
+        let pending = self.to_add.len();
+        self.to_add.retain(|(to_add, _, _)| to_add != name);
+        if self.to_add.len() != pending {
+            return Ok(());
+        }
+        
+        Err(format!("Unknown region: {}", name))
+    }
+    
+    // data is expected in the packer format, mipmaps have to be regenerated afterwards
+    pub fn update(&mut self, name: &str, data: &[u8], width: u32, height: u32) -> Result<(), String> {
+        let bpp = self.format.bytes_per_pixel() as usize;
+        let space = self.registered.get(name).ok_or(format!("Unknown region: {}", name))?;
+        
+        if width == 0 || height == 0 {
+            return Err(format!("Invalid data size for {}: {}x{}", name, width, height));
+        }
+        
+        let expected_size = width as usize * height as usize * bpp;
+        if data.len() < expected_size {
+            return Err(format!("Buffer too small for {}: got {} bytes, expected {} for {}x{} {}", name, data.len(), expected_size, width, height, self.format.name()));
+        }
+        
+        if space.width == 0 || space.height == 0 {
+            return Ok(());
+        }
+        
+        // scale the data to the space
+        let resampled;
+        let data = if width != space.width || height != space.height {
+            resampled = Self::resample(data, width, height, space.width, space.height, self.format, self.filter)
+                .map_err(|e| format!("Failed to resample {}: {}", name, e))?;
+            &resampled
+        } else {
+            data
+        };
+        
+        let layer = &mut self.layers[space.layer as usize];
+        let row_size = space.width as usize * bpp;
+        for y in 0..space.height {
+            let indexi = (y * space.width) as usize * bpp;
+            let indexo = ((space.y + y) * self.width + space.x) as usize * bpp;
+            layer[indexo..indexo + row_size].copy_from_slice(&data[indexi..indexi + row_size]);
+        }
+        
+        Ok(())
+    }
+    
+    pub fn set_filter(&mut self, filter: TexturePackerFilter) 
+    {
+        self.filter = filter;
+    }
+    
+    pub fn filter(&self) -> TexturePackerFilter 
+    {
+        self.filter
+    }
+    
+    // builds level_count - 1 downsampled levels for every layer
+    pub fn generate_mipmaps(&mut self, level_count: u32) -> Result<(), String> 
+    {
+        let max_levels = 32 - self.width.max(self.height).leading_zeros();
+        if level_count == 0 || level_count > max_levels {
+            return Err(format!("Invalid mip level count {}, must be between 1 and {}", level_count, max_levels));
+        }
+        
+        let (width, height, format) = (self.width, self.height, self.format);
+        self.mipmaps = self.layers.par_iter().map(|layer| -> Result<Vec<Vec<u8>>, String> {
+            let mut levels: Vec<Vec<u8>> = Vec::new();
+            let (mut level_width, mut level_height) = (width, height);
+            for _ in 1..level_count {
+                let previous = levels.last().unwrap_or(layer);
+                let (next_width, next_height) = ((level_width / 2).max(1), (level_height / 2).max(1));
+                let level = Self::resample(previous, level_width, level_height, next_width, next_height, format, TexturePackerFilter::Bilinear)?;
+                levels.push(level);
+                (level_width, level_height) = (next_width, next_height);
             }
+            Ok(levels)
+        }).collect::<Result<Vec<_>, String>>()?;
+        
+        Ok(())
+    }
+    
+    pub fn mip_level_count(&self) -> u32 
+    {
+        self.mipmaps.first().map(|levels| levels.len() as u32 + 1).unwrap_or(1)
+    }
+    
+    // level 0 is the layer itself
+    pub fn mip_data(&self, layer: u32, level: u32) -> Option<&[u8]> 
+    {
+        if level == 0 {
+            return self.layer_data(layer);
         }
+        self.mipmaps.get(layer as usize)?.get(level as usize - 1).map(|level| level.as_slice())
+    }
+    
+    pub fn region(&self, name: &str) -> Option<TexturePackerRegion> 
+    {
+        self.registered.get(name).map(|space| self.to_region(space))
+    }
+    
+    pub fn regions(&self) -> impl Iterator<Item = (&String, TexturePackerRegion)> + '_ 
+    {
+        self.registered.iter().map(|(name, space)| (name, self.to_region(space)))
+    }
+    
+    pub fn free_regions(&self) -> impl Iterator<Item = TexturePackerRegion> + '_ 
+    {
+        self.spaces.iter().map(|space| self.to_region(space))
+    }
+    
+    pub fn layer_data(&self, layer: u32) -> Option<&[u8]> 
+    {
+        self.layers.get(layer as usize).map(|layer| layer.as_slice())
+    }
+    
+    pub fn format(&self) -> TexturePackerFormat 
+    {
+        self.format
+    }
+    
+    pub fn size(&self) -> (u32, u32, u32) 
+    {
+        (self.width, self.height, self.depth)
     }
     
     pub fn pack(&mut self) -> Result<(), String> 
@@ -145,11 +391,8 @@ impl TexturePacker
         let to_add = self.to_add.clone();
         let mut could_not_pack = vec![];
         for (name, width, height) in to_add.iter().cloned() {
-            match self.pack_item(name.as_str(), width, height) {
-                Ok(_) => {},
-                Err(e) => {
-                    could_not_pack.push(name);
-                }
+            if self.pack_item(name.as_str(), width, height).is_err() {
+                could_not_pack.push(name);
             }
         }
         
@@ -164,6 +407,11 @@ impl TexturePacker
     
     fn pack_item(&mut self, name: &str, width: u32, height: u32) -> Result<(u32, u32), String> 
     {
+        // the old space of a replaced region goes back first, it would leak otherwise
+        if let Some(space) = self.registered.remove(name) {
+            self.spaces.push(space);
+        }
+        
         for index in (0..self.spaces.len()).rev() {
             let space = &mut self.spaces[index];
             if space.width < width || space.height < height {
@@ -235,6 +483,161 @@ impl TexturePacker
         Err("No space left".to_string())
     }
     
+    // hashes the packer settings along with the given inputs (font bytes, scales, ...). the filter and the mip level
+    // count are part of it, so they have to be set before the key is computed
+    // NOTE: DefaultHasher is not stable across rust versions, a toolchain update just means a cache miss
+    pub fn cache_key(&self, inputs: &[&[u8]]) -> u64 
+    {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        Self::CACHE_VERSION.hash(&mut hasher);
+        self.width.hash(&mut hasher);
+        self.height.hash(&mut hasher);
+        self.depth.hash(&mut hasher);
+        self.format.hash(&mut hasher);
+        self.filter.hash(&mut hasher);
+        self.mip_level_count().hash(&mut hasher);
+        for input in inputs.iter() {
+            input.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+    
+    pub fn save_cache(&self, dir: &str, key: u64) -> Result<(), String> 
+    {
+        if self.to_add.len() > 0 {
+            return Err(format!("Cannot cache packer with {} unpacked items", self.to_add.len()));
+        }
+        
+        let dir = std::path::Path::new(dir);
+        if !dir.exists() {
+            log::info!("Creating directory: {}", dir.display());
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        
+        for (index, layer) in self.layers.iter().enumerate() {
+            let path = dir.join(Self::cache_layer_file_name(key, index as u32));
+            let mut file = std::fs::File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+            file.write_all(layer).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        
+        let mut regions = serde_yaml::Mapping::new();
+        for (name, space) in self.registered.iter() {
+            regions.insert(serde_yaml::Value::String(name.clone()), space.to_yaml());
+        }
+        
+        let mut manifest = serde_yaml::Mapping::new();
+        manifest.insert("version".into(), Self::CACHE_VERSION.into());
+        manifest.insert("key".into(), format!("{:016x}", key).into());
+        manifest.insert("width".into(), self.width.into());
+        manifest.insert("height".into(), self.height.into());
+        manifest.insert("depth".into(), self.depth.into());
+        manifest.insert("format".into(), self.format.name().into());
+        manifest.insert("filter".into(), self.filter.name().into());
+        manifest.insert("mip_levels".into(), self.mip_level_count().into());
+        manifest.insert("regions".into(), serde_yaml::Value::Mapping(regions));
+        manifest.insert("spaces".into(), serde_yaml::Value::Sequence(self.spaces.iter().map(|space| space.to_yaml()).collect()));
+        
+        // the manifest is written last so a partially written cache is never considered valid
+        let path = dir.join(Self::cache_manifest_file_name(key));
+        let data = serde_yaml::to_string(&manifest).map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+        std::fs::write(&path, data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        
+        log::info!("Saved texture packer cache to: {}", path.display());
+        Ok(())
+    }
+    
+    pub async fn from_cache(dir: &str, key: u64) -> Result<Self, String> 
+    {
+        let dir = std::path::Path::new(dir);
+        let path = dir.join(Self::cache_manifest_file_name(key));
+        if !path.exists() {
+            return Err(format!("No cache found at: {}", path.display()));
+        }
+        
+        let data = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let manifest: serde_yaml::Value = serde_yaml::from_str(&data).map_err(|e| format!("Failed to parse manifest: {}", e))?;
+        
+        let read_u32 = |key: &str| -> Result<u32, String> {
+            let value = manifest[key].as_u64().ok_or(format!("Manifest is missing '{}'", key))?;
+            u32::try_from(value).map_err(|_| format!("Manifest value '{}' is out of range: {}", key, value))
+        };
+        
+        if read_u32("version")? != Self::CACHE_VERSION {
+            return Err("Cache version mismatch".to_string());
+        }
+        
+        if manifest["key"].as_str() != Some(format!("{:016x}", key).as_str()) {
+            return Err("Cache key mismatch".to_string());
+        }
+        
+        let width = read_u32("width")?;
+        let height = read_u32("height")?;
+        let depth = read_u32("depth")?;
+        let format = TexturePackerFormat::from_name(manifest["format"].as_str().ok_or("Manifest is missing 'format'")?)?;
+        let filter = TexturePackerFilter::from_name(manifest["filter"].as_str().ok_or("Manifest is missing 'filter'")?)?;
+        let mip_levels = read_u32("mip_levels")?;
+        
+        let mut registered = std::collections::HashMap::new();
+        for (name, value) in manifest["regions"].as_mapping().ok_or("Manifest is missing 'regions'")?.iter() {
+            let name = name.as_str().ok_or("Invalid region name in manifest")?;
+            registered.insert(name.to_string(), TexturePackerSpace::from_yaml(value)?);
+        }
+        
+        let mut spaces = Vec::new();
+        for value in manifest["spaces"].as_sequence().ok_or("Manifest is missing 'spaces'")?.iter() {
+            spaces.push(TexturePackerSpace::from_yaml(value)?);
+        }
+        
+        // the manifest comes from disk, so the bounds are checked without trusting the values to not overflow
+        let in_bounds = |space: &TexturePackerSpace| {
+            space.layer < depth
+                && space.x.checked_add(space.width).is_some_and(|right| right <= width)
+                && space.y.checked_add(space.height).is_some_and(|bottom| bottom <= height)
+        };
+        if !registered.values().chain(spaces.iter()).all(in_bounds) {
+            return Err("Cache contains out of bounds spaces".to_string());
+        }
+        
+        let layer_size = width.checked_mul(height).and_then(|size| size.checked_mul(format.bytes_per_pixel()))
+            .ok_or(format!("Cache layer size {}x{} is out of range", width, height))? as usize;
+        let mut layers = Vec::new();
+        for index in 0..depth {
+            let path = dir.join(Self::cache_layer_file_name(key, index));
+            let mut file = std::fs::File::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+            let file_size = file.metadata().map_err(|e| format!("Failed to read {}: {}", path.display(), e))?.len();
+            if file_size != layer_size as u64 {
+                return Err(format!("Layer {} has size {}, expected {}", index, file_size, layer_size));
+            }
+            let mut layer = Vec::with_capacity(layer_size);
+            file.read_to_end(&mut layer).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if layer.len() != layer_size {
+                return Err(format!("Layer {} has size {}, expected {}", index, layer.len(), layer_size));
+            }
+            layers.push(layer);
+        }
+        
+        log::info!("Loaded texture packer cache from: {}", path.display());
+        
+        let mut packer = Self {
+            width           : width,
+            height          : height,
+            depth           : depth,
+            format          : format,
+            filter          : filter,
+            layers          : layers,
+            mipmaps         : Vec::new(),
+            spaces          : spaces,
+            registered      : registered,
+            to_add          : Vec::new(),
+        };
+        
+        // mipmaps are not cached, they are cheap to rebuild from the layers
+        if mip_levels > 1 {
+            packer.generate_mipmaps(mip_levels)?;
+        }
+        Ok(packer)
+    }
+    
     // for visualizing the texture packer
     pub fn fill_color(&mut self) -> Result<(), String> 
     {
@@ -244,16 +647,14 @@ impl TexturePacker
             layer.par_iter_mut().for_each(|pixel| *pixel = 0);
         }
         
+        let bpp = self.format.bytes_per_pixel() as usize;
         for (name, space) in self.registered.iter() {
             let color = Self::name_color(name.as_str());
             let layer = &mut self.layers[space.layer as usize];
             for y in 0..space.height {
                 for x in 0..space.width {
-                    let index = ((space.y + y) * self.width + (space.x + x)) as usize * 4;
-                    layer[index + 0] = (color >> 24) as u8;
-                    layer[index + 1] = (color >> 16) as u8;
-                    layer[index + 2] = (color >> 8) as u8;
-                    layer[index + 3] = (color >> 0) as u8;
+                    let index = ((space.y + y) * self.width + (space.x + x)) as usize * bpp;
+                    layer[index..index + bpp].copy_from_slice(&color.to_be_bytes()[..bpp]);
                 }
             }
         }
@@ -269,16 +670,14 @@ impl TexturePacker
             layer.par_iter_mut().for_each(|pixel| *pixel = 0);
         }
         
+        let bpp = self.format.bytes_per_pixel() as usize;
         for space in self.spaces.iter() {
             let color = rand::random::<u32>() | 0x000000FF;
             let layer = &mut self.layers[space.layer as usize];
             for y in 0..space.height {
                 for x in 0..space.width {
-                    let index = ((space.y + y) * self.width + (space.x + x)) as usize * 4;
-                    layer[index + 0] = (color >> 24) as u8;
-                    layer[index + 1] = (color >> 16) as u8;
-                    layer[index + 2] = (color >> 8) as u8;
-                    layer[index + 3] = (color >> 0) as u8;
+                    let index = ((space.y + y) * self.width + (space.x + x)) as usize * bpp;
+                    layer[index..index + bpp].copy_from_slice(&color.to_be_bytes()[..bpp]);
                 }
             }
         }
@@ -307,4 +706,54 @@ impl TexturePacker
         hasher.finish() as u32 | 0x000000FF
     }
     
+    fn resample(data: &[u8], width: u32, height: u32, new_width: u32, new_height: u32, format: TexturePackerFormat, filter: TexturePackerFilter) -> Result<Vec<u8>, String> 
+    {
+        let size = (width * height * format.bytes_per_pixel()) as usize;
+        if data.len() < size {
+            return Err(format!("Data has size {}, expected {}", data.len(), size));
+        }
+        
+        let data = data[..size].to_vec();
+        let resampled = match format {
+            TexturePackerFormat::R8 => {
+                let img = image::GrayImage::from_raw(width, height, data).ok_or("Failed to create image")?;
+                image::imageops::resize(&img, new_width, new_height, filter.filter_type()).into_raw()
+            },
+            TexturePackerFormat::Rgba8 => {
+                let img = image::RgbaImage::from_raw(width, height, data).ok_or("Failed to create image")?;
+                image::imageops::resize(&img, new_width, new_height, filter.filter_type()).into_raw()
+            },
+        };
+        
+        Ok(resampled)
+    }
+    
+    fn to_region(&self, space: &TexturePackerSpace) -> TexturePackerRegion 
+    {
+        TexturePackerRegion {
+            x           : space.x,
+            y           : space.y,
+            width       : space.width,
+            height      : space.height,
+            layer       : space.layer,
+            uv          : (
+                            space.x as f32 / self.width as f32,
+                            space.y as f32 / self.height as f32,
+                            space.width as f32 / self.width as f32,
+                            space.height as f32 / self.height as f32,
+                        ),
+            format      : self.format,
+        }
+    }
+    
+    fn cache_manifest_file_name(key: u64) -> String 
+    {
+        format!("atlas_{:016x}.yml", key)
+    }
+    
+    fn cache_layer_file_name(key: u64, layer: u32) -> String 
+    {
+        format!("atlas_{:016x}_{}.bin", key, layer)
+    }
+    
 }
\ No newline at end of file
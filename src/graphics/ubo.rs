@@ -13,14 +13,14 @@ impl<T> UniformBuffer<T>
 {
     pub async fn new(instance: &crate::GPUInstance, label: Option<&str>) -> Result<Self, String> {
         let buffer = instance.device().create_buffer(&wgpu::BufferDescriptor {
-            label               : label.clone(),
+            label               : label,
             size                : std::mem::size_of::<T>() as u64,
             usage               : wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation  : false,
         });
 
         let layout = instance.device().create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label       : label.clone(),
+            label       : label,
             entries     : &[wgpu::BindGroupLayoutEntry {
                 binding     : 0,
                 visibility  : wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
@@ -34,7 +34,7 @@ impl<T> UniformBuffer<T>
         });
 
         let bind_group = instance.device().create_bind_group(&wgpu::BindGroupDescriptor {
-            label       : label.clone(),
+            label       : label,
             layout      : &layout,
             entries     : &[wgpu::BindGroupEntry {
                 binding     : 0,
@@ -87,4 +87,124 @@ impl<T> UniformBuffer<T>
         std::mem::size_of::<T>() as u64
     }
 
-}
\ No newline at end of file
+}
+
+// several T in one buffer, each bound with a dynamic offset, so every draw or pass of a submission can have its own
+// data. slots are handed out round robin, reset frees them once the submission using them has been made
+pub struct UniformRingBuffer<T>
+    where T: Copy + Clone + Default + bytemuck::Pod + bytemuck::Zeroable
+{
+    buffer          : wgpu::Buffer,
+    bind_group      : wgpu::BindGroup,
+    layout          : wgpu::BindGroupLayout,
+    stride          : u64,
+    slot_count      : u32,
+    next_slot       : u32,
+    used_slots      : u32,
+    _phantom        : std::marker::PhantomData<T>,
+}
+
+impl<T> UniformRingBuffer<T>
+    where T: Copy + Clone + Default + bytemuck::Pod + bytemuck::Zeroable
+{
+    pub async fn new(instance: &crate::GPUInstance, slot_count: u32, label: Option<&str>) -> Result<Self, String> {
+        if slot_count == 0 {
+            return Err("Uniform ring buffer needs at least one slot".to_string());
+        }
+
+        let size = std::mem::size_of::<T>() as u64;
+        let alignment = instance.device().limits().min_uniform_buffer_offset_alignment as u64;
+        let stride = size.div_ceil(alignment) * alignment;
+
+        let buffer = instance.device().create_buffer(&wgpu::BufferDescriptor {
+            label               : label,
+            size                : stride * slot_count as u64,
+            usage               : wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation  : false,
+        });
+
+        let layout = instance.device().create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label       : label,
+            entries     : &[wgpu::BindGroupLayoutEntry {
+                binding     : 0,
+                visibility  : wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                ty          : wgpu::BindingType::Buffer {
+                                ty                  : wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset  : true,
+                                min_binding_size    : wgpu::BufferSize::new(size),
+                            },
+                count       : None,
+            }],
+        });
+
+        // the binding covers a single slot, the dynamic offset picks which one
+        let bind_group = instance.device().create_bind_group(&wgpu::BindGroupDescriptor {
+            label       : label,
+            layout      : &layout,
+            entries     : &[wgpu::BindGroupEntry {
+                binding     : 0,
+                resource    : wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                                buffer      : &buffer,
+                                offset      : 0,
+                                size        : wgpu::BufferSize::new(size),
+                            }),
+            }],
+        });
+
+        Ok(Self {
+            buffer          : buffer,
+            bind_group      : bind_group,
+            layout          : layout,
+            stride          : stride,
+            slot_count      : slot_count,
+            next_slot       : 0,
+            used_slots      : 0,
+            _phantom        : std::marker::PhantomData,
+        })
+    }
+
+    // writes data into the next free slot and returns the dynamic offset to bind it with
+    pub fn push(&mut self, instance: &crate::GPUInstance, data: T) -> Result<u32, String> {
+        if self.used_slots >= self.slot_count {
+            return Err(format!("Uniform ring buffer is full ({} slots), reset it after submitting", self.slot_count));
+        }
+
+        let offset = self.next_slot as u64 * self.stride;
+        instance.queue().write_buffer(&self.buffer, offset, bytemuck::cast_slice(&[data]));
+
+        self.next_slot = (self.next_slot + 1) % self.slot_count;
+        self.used_slots += 1;
+        Ok(offset as u32)
+    }
+
+    // makes all slots available again, the next pushes continue after the last one used
+    pub fn reset(&mut self) {
+        self.used_slots = 0;
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    pub fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    // distance between slots in bytes
+    pub fn stride(&self) -> u64 {
+        self.stride
+    }
+
+    pub fn slot_count(&self) -> u32 {
+        self.slot_count
+    }
+
+    pub fn used_slots(&self) -> u32 {
+        self.used_slots
+    }
+
+}
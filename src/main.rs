@@ -5,18 +5,28 @@ async fn main() -> Result<(), String> {
     }));
 
     env_logger::builder().filter_level(log::LevelFilter::Info).init();
-    let event_loop = winit::event_loop::EventLoop::new();
-    let mut app = xettacast::App::new(&event_loop).await.unwrap();
+    let command_line = xettacast::CommandLine::parse(std::env::args())?;
+    let event_loop = winit::event_loop::EventLoopBuilder::<xettacast::AppEvent>::with_user_event().build();
+
+    if command_line.command == xettacast::CommandLineCommand::GpuInfo {
+        print!("{}", xettacast::App::gpu_info(&event_loop, &command_line).await?);
+        return Ok(());
+    }
+
+    let mut app = xettacast::App::new(&event_loop, command_line).await.unwrap();
 
     event_loop.run(move |event, _, control_flow| {
         app.global_update().unwrap();
         match event {
             winit::event::Event::MainEventsCleared => {
-                app.request_redraw();
+                *control_flow = app.on_main_events_cleared();
             },
             winit::event::Event::WindowEvent { event, window_id: _ } => {
                 app.on_event(event);
             },
+            winit::event::Event::UserEvent(event) => {
+                app.on_app_event(event);
+            },
             winit::event::Event::RedrawRequested(_) => {
                 if !app.on_update().unwrap() {
                     *control_flow = winit::event_loop::ControlFlow::Exit;
@@ -24,6 +34,9 @@ async fn main() -> Result<(), String> {
             },
             _ => {}
         }
+        if !app.is_running() {
+            *control_flow = winit::event_loop::ControlFlow::Exit;
+        }
 
     });
 }
@@ -0,0 +1,49 @@
+fn parse(args: &[&str]) -> Result<xettacast::CommandLine, String> {
+    xettacast::CommandLine::parse(std::iter::once("xettacast").chain(args.iter().copied()).map(|arg| arg.to_string()))
+}
+
+#[test]
+fn defaults_leave_the_config_alone() {
+    let command_line = parse(&[]).unwrap();
+    assert_eq!(command_line.command, xettacast::CommandLineCommand::Run);
+    assert!(!command_line.debug_atlas);
+    assert_eq!(command_line.present_mode, None);
+    assert_eq!(command_line.backend, None);
+    assert_eq!(command_line.power_preference, None);
+    assert!(!command_line.fallback_adapter);
+}
+
+#[test]
+fn gpu_options_and_command() {
+    let command_line = parse(&["gpu-info", "--present-mode", "vsync", "--backend", "gl", "--power-preference", "low_power", "--fallback-adapter"]).unwrap();
+    assert_eq!(command_line.command, xettacast::CommandLineCommand::GpuInfo);
+    assert_eq!(command_line.present_mode, Some(xettacast::SwapchainPresentMode::Vsync));
+    assert_eq!(command_line.backend, Some(wgpu::Backends::GL));
+    assert_eq!(command_line.power_preference, Some(wgpu::PowerPreference::LowPower));
+    assert!(command_line.fallback_adapter);
+}
+
+#[test]
+fn bad_values_are_rejected() {
+    assert!(parse(&["--backend"]).is_err());
+    assert!(parse(&["--backend", "glide"]).is_err());
+    assert!(parse(&["--backend", "vulkan,glide"]).is_err());
+    assert!(parse(&["--present-mode", "fast"]).is_err());
+    assert!(parse(&["--power-preference", "max"]).is_err());
+    assert!(parse(&["--unknown"]).is_err());
+}
+
+#[test]
+fn names_round_trip() {
+    for name in ["primary", "all", "vulkan", "gl", "metal", "dx12", "dx11", "webgpu", "vulkan,gl", "gl,dx12"] {
+        let backends = xettacast::GPUInstanceOptions::parse_backends(name).unwrap();
+        assert_eq!(xettacast::GPUInstanceOptions::backends_name(backends), name);
+    }
+    for name in ["high_performance", "low_power", "none"] {
+        let power_preference = xettacast::GPUInstanceOptions::parse_power_preference(name).unwrap();
+        assert_eq!(xettacast::GPUInstanceOptions::power_preference_name(power_preference), name);
+    }
+    for name in ["auto", "vsync", "mailbox", "immediate"] {
+        assert_eq!(xettacast::SwapchainPresentMode::from_name(name).unwrap().name(), name);
+    }
+}
@@ -0,0 +1,22 @@
+// Shared by the suites that need a GPU. They fail when there is no fallback adapter, set XETTACAST_SKIP_GPU_TESTS=1
+// to skip them on purpose instead.
+
+// the GL backend can't create and drop instances from several threads at once, the tests take turns on the GPU
+static GPU_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+// the lock is handed out with the instance and must outlive it
+pub fn instance() -> Option<(std::sync::MutexGuard<'static, ()>, xettacast::GPUInstance)> {
+    let lock = GPU_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    match pollster::block_on(xettacast::GPUInstance::new_headless(xettacast::GPUInstanceOptions {
+        backends                : wgpu::Backends::all(),
+        force_fallback_adapter  : true,
+        ..Default::default()
+    })) {
+        Ok(instance) => Some((lock, instance)),
+        Err(e) if std::env::var("XETTACAST_SKIP_GPU_TESTS").is_ok() => {
+            eprintln!("Skipping GPU test, XETTACAST_SKIP_GPU_TESTS is set and no adapter is available: {}", e);
+            None
+        },
+        Err(e) => panic!("No adapter for the GPU tests ({}), set XETTACAST_SKIP_GPU_TESTS=1 to skip them", e),
+    }
+}
@@ -0,0 +1,358 @@
+// Renders small scenes offscreen and compares them with the PNGs in tests/golden.
+// Run with XETTACAST_BLESS=1 to (re)generate the reference images after an intended change,
+// failures write the actual and diff images to target/golden.
+
+mod common;
+
+const SIZE: u32 = 256;
+
+// per channel difference that still counts as a match, adapters differ slightly in rasterization
+const CHANNEL_TOLERANCE: u8 = 3;
+
+// fraction of pixels allowed to be outside the channel tolerance
+const PIXEL_TOLERANCE: f32 = 0.002;
+
+fn golden_dir() -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn output_dir() -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("target/golden")
+}
+
+// drawn in order, with a flush between each
+type Scenes<'a> = Vec<Box<dyn FnOnce(&mut xettacast::Renderer) + 'a>>;
+
+fn render(sample_count: u32, scene: impl FnOnce(&mut xettacast::Renderer)) -> Option<image::RgbaImage> {
+    render_flushes(sample_count, vec![Box::new(scene)])
+}
+
+// the renderer is flushed after every scene but the last, which goes out with end
+fn render_flushes<'a>(sample_count: u32, scenes: Scenes<'a>) -> Option<image::RgbaImage> {
+    let (_lock, instance) = common::instance()?;
+
+    let target = pollster::block_on(xettacast::RenderTarget::new(&instance, SIZE, SIZE, wgpu::TextureFormat::Rgba8Unorm, Some("GoldenTarget"))).unwrap();
+    let mut renderer = pollster::block_on(xettacast::Renderer::new(&instance, target.format(), sample_count)).unwrap();
+
+    renderer.begin().unwrap();
+    renderer.set_frame_res(target.size());
+    renderer.set_target_view(target.create_view());
+    let scene_count = scenes.len();
+    for (index, scene) in scenes.into_iter().enumerate() {
+        scene(&mut renderer);
+        if index + 1 < scene_count {
+            renderer.flush(&instance).unwrap();
+        }
+    }
+    renderer.end(&instance).unwrap();
+
+    Some(pollster::block_on(target.read_back(&instance)).unwrap())
+}
+
+fn check(name: &str, scene: impl FnOnce(&mut xettacast::Renderer)) {
+    check_with_samples(name, 1, scene);
+}
+
+fn check_with_samples(name: &str, sample_count: u32, scene: impl FnOnce(&mut xettacast::Renderer)) {
+    compare(name, render(sample_count, scene));
+}
+
+fn check_flushes<'a>(name: &str, scenes: Scenes<'a>) {
+    compare(name, render_flushes(1, scenes));
+}
+
+fn compare(name: &str, actual: Option<image::RgbaImage>) {
+    let actual = match actual {
+        Some(actual) => actual,
+        None => return,
+    };
+
+    let golden_path = golden_dir().join(format!("{}.png", name));
+    if std::env::var("XETTACAST_BLESS").is_ok() {
+        std::fs::create_dir_all(golden_dir()).unwrap();
+        actual.save(&golden_path).unwrap();
+        return;
+    }
+
+    std::fs::create_dir_all(output_dir()).unwrap();
+    let actual_path = output_dir().join(format!("{}.actual.png", name));
+
+    let expected = match image::open(&golden_path) {
+        Ok(expected) => expected.to_rgba8(),
+        Err(e) => {
+            actual.save(&actual_path).unwrap();
+            panic!("Missing golden image {} ({}), rendered output written to {}, run with XETTACAST_BLESS=1 to accept it",
+                golden_path.display(), e, actual_path.display());
+        }
+    };
+
+    assert_eq!(expected.dimensions(), actual.dimensions(), "{}: golden image size mismatch", name);
+
+    let mut diff = image::RgbaImage::new(actual.width(), actual.height());
+    let mut mismatched = 0;
+    for (x, y, pixel) in actual.enumerate_pixels() {
+        let other = expected.get_pixel(x, y);
+        let delta = pixel.0.iter().zip(other.0.iter()).map(|(a, b)| a.abs_diff(*b)).max().unwrap_or(0);
+        if delta > CHANNEL_TOLERANCE {
+            mismatched += 1;
+            diff.put_pixel(x, y, image::Rgba([255, 0, 0, 255]));
+        } else {
+            diff.put_pixel(x, y, image::Rgba([pixel.0[0] / 4, pixel.0[1] / 4, pixel.0[2] / 4, 255]));
+        }
+    }
+
+    let allowed = (PIXEL_TOLERANCE * (actual.width() * actual.height()) as f32) as u32;
+    if mismatched > allowed {
+        let diff_path = output_dir().join(format!("{}.diff.png", name));
+        actual.save(&actual_path).unwrap();
+        diff.save(&diff_path).unwrap();
+        panic!("{}: {} pixels differ from the golden image (allowed {}), see {} and {}",
+            name, mismatched, allowed, actual_path.display(), diff_path.display());
+    }
+}
+
+#[test]
+fn golden_rects() {
+    check("rects", |renderer| {
+        renderer.set_color(1.0, 0.0, 0.0, 1.0);
+        renderer.rectp(16.0, 16.0, 96.0, 64.0);
+        renderer.set_color(0.0, 1.0, 0.0, 0.5);
+        renderer.rectp(64.0, 48.0, 128.0, 128.0);
+        renderer.set_color(0.0, 0.0, 1.0, 1.0);
+        renderer.rect(0.5, 0.75, 0.4, 0.2);
+    });
+}
+
+#[test]
+fn golden_rounded_corners() {
+    check("rounded_corners", |renderer| {
+        renderer.set_color(1.0, 1.0, 0.0, 1.0);
+        renderer.set_border_radius(10.0, 10.0, 20.0, 48.0);
+        renderer.rectp(16.0, 16.0, 224.0, 96.0);
+        renderer.set_color(0.0, 1.0, 1.0, 1.0);
+        renderer.set_border_radius(48.0, 0.0, 48.0, 0.0);
+        renderer.rectp(64.0, 144.0, 128.0, 96.0);
+    });
+}
+
+#[test]
+fn golden_mask() {
+    check("mask", |renderer| {
+        renderer.set_maskp(32.0, 32.0, 160.0, 120.0);
+        renderer.set_color(1.0, 0.5, 0.0, 1.0);
+        renderer.rectp(0.0, 0.0, 128.0, 128.0);
+        renderer.set_color(0.5, 0.0, 1.0, 1.0);
+        renderer.rectp(128.0, 96.0, 128.0, 128.0);
+    });
+}
+
+#[test]
+fn golden_mask_radius() {
+    check("mask_radius", |renderer| {
+        renderer.set_maskp(32.0, 32.0, 192.0, 192.0);
+        renderer.set_mask_border_radius(48.0, 24.0, 48.0, 24.0);
+        renderer.set_color(1.0, 1.0, 1.0, 1.0);
+        renderer.rectp(0.0, 0.0, 256.0, 256.0);
+    });
+}
+
+#[test]
+fn golden_depth() {
+    check("depth", |renderer| {
+        renderer.set_depth(0.75);
+        renderer.set_color(1.0, 0.0, 0.0, 1.0);
+        renderer.rectp(32.0, 32.0, 128.0, 128.0);
+        renderer.set_depth(0.25);
+        renderer.set_color(0.0, 0.0, 1.0, 0.75);
+        renderer.rectp(96.0, 96.0, 128.0, 128.0);
+    });
+}
+
+#[test]
+fn golden_stroke_shadow_gradient() {
+    check("stroke_shadow_gradient", |renderer| {
+        renderer.set_border_radius(12.0, 12.0, 12.0, 12.0);
+        renderer.set_color(0.0, 0.0, 0.0, 0.6);
+        renderer.shadow_rectp(32.0, 144.0, 192.0, 80.0, 8.0, (4.0, -4.0));
+        renderer.gradient_rectp(32.0, 144.0, 192.0, 80.0, xettacast::RendererGradient::Linear {
+            from        : (1.0, 0.2, 0.2, 1.0),
+            to          : (0.2, 0.2, 1.0, 1.0),
+            start       : (0.0, 0.0),
+            end         : (1.0, 1.0),
+        });
+        renderer.set_color(1.0, 1.0, 1.0, 1.0);
+        renderer.stroke_rectp(32.0, 32.0, 192.0, 80.0, 3.0);
+        renderer.gradient_rectp(48.0, 48.0, 160.0, 48.0, xettacast::RendererGradient::Radial {
+            from        : (1.0, 1.0, 0.0, 1.0),
+            to          : (0.0, 0.5, 0.0, 0.0),
+            center      : (0.5, 0.5),
+            radius      : 60.0,
+        });
+    });
+}
+
+#[test]
+fn golden_shapes() {
+    check("shapes", |renderer| {
+        renderer.set_color(1.0, 0.5, 0.0, 1.0);
+        renderer.circlep(64.0, 192.0, 40.0);
+        renderer.set_color(0.0, 0.8, 1.0, 1.0);
+        renderer.stroke_ellipsep(128.0, 160.0, 112.0, 64.0, 4.0);
+        renderer.set_color(1.0, 1.0, 1.0, 1.0);
+        renderer.linep(16.0, 16.0, 240.0, 120.0, 3.5);
+        renderer.polylinep(&[(16.0, 100.0), (64.0, 40.0), (112.0, 100.0), (160.0, 40.0)], 6.0);
+        renderer.set_color(0.2, 1.0, 0.2, 1.0);
+        renderer.arcp(200.0, 60.0, 32.0, 0.0, std::f32::consts::PI * 1.5, 8.0);
+    });
+}
+
+#[test]
+fn golden_layers() {
+    check("layers", |renderer| {
+        // submitted top to bottom, must still come out as background, content, tooltip
+        renderer.set_layer(xettacast::RendererLayer::Tooltip);
+        renderer.set_color(1.0, 1.0, 0.0, 0.8);
+        renderer.rectp(128.0, 128.0, 96.0, 96.0);
+        renderer.set_layer(xettacast::RendererLayer::Content);
+        renderer.set_color(0.0, 0.0, 1.0, 0.75);
+        renderer.rectp(80.0, 80.0, 96.0, 96.0);
+        renderer.set_layer(xettacast::RendererLayer::Background);
+        renderer.set_color(1.0, 0.0, 0.0, 1.0);
+        renderer.rectp(32.0, 32.0, 96.0, 96.0);
+
+        // within a layer the nearer depth wins even when submitted first
+        renderer.set_layer(xettacast::RendererLayer::Content);
+        renderer.set_depth(0.1);
+        renderer.set_color(0.0, 1.0, 0.0, 0.75);
+        renderer.rectp(16.0, 176.0, 64.0, 64.0);
+        renderer.set_depth(0.9);
+        renderer.set_color(1.0, 0.0, 1.0, 1.0);
+        renderer.rectp(48.0, 160.0, 64.0, 64.0);
+    });
+}
+
+// layers only order the items of one flush, whatever was flushed before stays below
+#[test]
+fn golden_layers_across_flush() {
+    check_flushes("layers_across_flush", vec![
+        Box::new(|renderer: &mut xettacast::Renderer| {
+            renderer.set_layer(xettacast::RendererLayer::Tooltip);
+            renderer.set_color(1.0, 1.0, 0.0, 1.0);
+            renderer.rectp(48.0, 48.0, 112.0, 112.0);
+            renderer.set_layer(xettacast::RendererLayer::Background);
+            renderer.set_color(0.0, 0.6, 0.0, 1.0);
+            renderer.rectp(16.0, 16.0, 112.0, 112.0);
+        }),
+        Box::new(|renderer: &mut xettacast::Renderer| {
+            renderer.set_layer(xettacast::RendererLayer::Background);
+            renderer.set_color(0.0, 0.0, 1.0, 0.75);
+            renderer.rectp(96.0, 96.0, 128.0, 128.0);
+        }),
+    ]);
+}
+
+#[test]
+fn golden_scale_factor() {
+    check("scale_factor", |renderer| {
+        // 128x128 logical pixels on the 256x256 target, the 1px lines and odd offsets must stay crisp
+        renderer.set_scale_factor(2.0);
+        renderer.set_color(0.2, 0.4, 1.0, 1.0);
+        renderer.set_border_radius(8.0, 8.0, 8.0, 8.0);
+        renderer.rectp(8.25, 8.25, 48.5, 32.0);
+        renderer.set_border_radius(0.0, 0.0, 0.0, 0.0);
+        renderer.set_color(1.0, 1.0, 1.0, 1.0);
+        renderer.linep(8.0, 64.0, 120.0, 64.0, 0.5);
+        renderer.linep(64.0, 72.0, 64.0, 120.0, 1.0);
+        renderer.stroke_rectp(72.0, 8.0, 48.0, 48.0, 1.0);
+    });
+}
+
+#[test]
+fn golden_msaa() {
+    check_with_samples("msaa", 4, |renderer| {
+        renderer.set_color(1.0, 0.8, 0.2, 1.0);
+        renderer.translate(128.0, 128.0);
+        renderer.rotate(0.4);
+        renderer.rectp(-64.0, -32.0, 128.0, 64.0);
+        renderer.set_color(0.2, 0.6, 1.0, 0.8);
+        renderer.rotate(1.0);
+        renderer.rectp(-48.0, -48.0, 96.0, 96.0);
+    });
+}
+
+#[test]
+fn golden_backdrop() {
+    check("backdrop", |renderer| {
+        // stripes to blur, then a frosted panel above them
+        for i in 0..8 {
+            renderer.set_color(if i % 2 == 0 { 1.0 } else { 0.1 }, 0.3, if i % 2 == 0 { 0.1 } else { 1.0 }, 1.0);
+            renderer.rectp(i as f32 * 32.0, 0.0, 32.0, 256.0);
+        }
+
+        renderer.set_layer(xettacast::RendererLayer::Overlay);
+        renderer.set_border_radius(16.0, 16.0, 16.0, 16.0);
+        renderer.set_color(1.0, 1.0, 1.0, 0.2);
+        renderer.backdrop_rectp(48.0, 64.0, 160.0, 128.0, 12.0);
+        renderer.set_border_radius(0.0, 0.0, 0.0, 0.0);
+        renderer.set_color(0.0, 0.0, 0.0, 1.0);
+        renderer.rectp(64.0, 120.0, 128.0, 16.0);
+    });
+}
+
+#[test]
+fn golden_backdrop_runs() {
+    check("backdrop_runs", |renderer| {
+        // a checkerboard, so both blur axes show
+        for i in 0..64 {
+            let (x, y) = ((i % 8) as f32 * 32.0, (i / 8) as f32 * 32.0);
+            renderer.set_color(if (i + i / 8) % 2 == 0 { 1.0 } else { 0.1 }, 0.3, if (i + i / 8) % 2 == 0 { 0.1 } else { 1.0 }, 1.0);
+            renderer.rectp(x, y, 32.0, 32.0);
+        }
+
+        // the first two share a blur, the third overlaps the first and has to see it
+        renderer.set_layer(xettacast::RendererLayer::Overlay);
+        renderer.set_color(1.0, 1.0, 1.0, 0.3);
+        renderer.backdrop_rectp(16.0, 16.0, 80.0, 64.0, 8.0);
+        renderer.backdrop_rectp(160.0, 16.0, 80.0, 64.0, 8.0);
+        renderer.set_color(0.0, 0.0, 0.0, 0.3);
+        renderer.backdrop_rectp(56.0, 48.0, 80.0, 64.0, 8.0);
+
+        // the blurred region has to follow the rotation
+        renderer.set_color(1.0, 1.0, 1.0, 0.1);
+        renderer.set_rotation(0.5);
+        renderer.backdrop_rectp(80.0, 160.0, 112.0, 48.0, 16.0);
+        renderer.set_rotation(0.0);
+    });
+}
+
+// with msaa, a flush after a backdrop flush must not resolve the msaa texture (which only has the backdrop flush)
+// over the target, that would drop everything flushed before the backdrop
+#[test]
+fn msaa_flush_after_backdrop() {
+    let left = |renderer: &mut xettacast::Renderer| {
+        renderer.set_color(0.2, 0.9, 0.3, 1.0);
+        renderer.rectp(8.0, 8.0, 48.0, 240.0);
+    };
+    let backdrop = |renderer: &mut xettacast::Renderer| {
+        renderer.set_color(1.0, 0.3, 0.1, 1.0);
+        renderer.rectp(128.0, 0.0, 32.0, 256.0);
+        renderer.set_color(1.0, 1.0, 1.0, 0.2);
+        renderer.backdrop_rectp(112.0, 64.0, 128.0, 128.0, 4.0);
+    };
+    let label = |renderer: &mut xettacast::Renderer| {
+        renderer.set_color(0.0, 0.0, 0.0, 1.0);
+        renderer.rectp(120.0, 120.0, 112.0, 16.0);
+    };
+
+    let single = match render(4, |renderer| { left(renderer); backdrop(renderer); label(renderer); }) {
+        Some(single) => single,
+        None => return,
+    };
+    let flushed = render_flushes(4, vec![Box::new(left), Box::new(backdrop), Box::new(label)]).unwrap();
+
+    let mismatched = single.pixels().zip(flushed.pixels())
+        .filter(|(a, b)| a.0.iter().zip(b.0.iter()).any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE))
+        .count() as u32;
+    let allowed = (PIXEL_TOLERANCE * (SIZE * SIZE) as f32) as u32;
+    assert!(mismatched <= allowed, "{} pixels differ between one flush and three (allowed {})", mismatched, allowed);
+}
@@ -0,0 +1,93 @@
+// Exercises ShaderStorageBuffer growth, range uploads and readback, and a ComputePipeline writing into one.
+// Needs the fallback adapter like the golden image tests, see tests/common.
+
+mod common;
+
+const DOUBLE_SHADER: &str = "
+@group(0) @binding(0) var<storage, read_write> values : array<u32>;
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= arrayLength(&values)) {
+        return;
+    }
+    values[id.x] = values[id.x] * 2u;
+}
+";
+
+#[test]
+fn upload_grow_and_read_back() {
+    let (_lock, instance) = match common::instance() {
+        Some(instance) => instance,
+        None => return,
+    };
+
+    let mut buffer = pollster::block_on(xettacast::ShaderStorageBuffer::<u32>::new(&instance, 4, Some("Test"))).unwrap();
+    assert_eq!(buffer.capacity(), 4);
+    assert!(buffer.is_empty());
+
+    buffer.upload(&instance, &[1, 2, 3]).unwrap();
+    assert_eq!(buffer.len(), 3);
+
+    // appending past the capacity reallocates and keeps what was there
+    let tail = (4..=20).collect::<Vec<u32>>();
+    buffer.upload_range(&instance, 3, &tail).unwrap();
+    assert_eq!(buffer.len(), 20);
+    assert!(buffer.capacity() >= 20);
+    assert_eq!(pollster::block_on(buffer.read_back(&instance)).unwrap(), (1..=20).collect::<Vec<u32>>());
+
+    // overwriting a range in the middle leaves the rest alone
+    buffer.upload_range(&instance, 5, &[100, 101]).unwrap();
+    let data = pollster::block_on(buffer.read_back(&instance)).unwrap();
+    assert_eq!(&data[4..8], &[5, 100, 101, 8]);
+
+    // gaps are not allowed
+    assert!(buffer.upload_range(&instance, 21, &[1]).is_err());
+
+    // a full upload replaces the length as well
+    buffer.upload(&instance, &[7]).unwrap();
+    assert_eq!(pollster::block_on(buffer.read_back(&instance)).unwrap(), vec![7]);
+}
+
+#[test]
+fn unaligned_upload_is_rejected() {
+    let (_lock, instance) = match common::instance() {
+        Some(instance) => instance,
+        None => return,
+    };
+
+    let mut buffer = pollster::block_on(xettacast::ShaderStorageBuffer::<u16>::new(&instance, 8, Some("Test"))).unwrap();
+    assert!(buffer.upload(&instance, &[1, 2, 3]).is_err());
+    assert!(buffer.upload(&instance, &[1, 2, 3, 4]).is_ok());
+    assert!(buffer.upload_range(&instance, 1, &[5, 6]).is_err());
+}
+
+#[test]
+fn compute_doubles_values() {
+    let (_lock, instance) = match common::instance() {
+        Some(instance) => instance,
+        None => return,
+    };
+
+    let count = 1000u32;
+    let mut buffer = pollster::block_on(xettacast::ShaderStorageBuffer::<u32>::new_read_write(&instance, count as usize, Some("Values"))).unwrap();
+    buffer.upload(&instance, &(0..count).collect::<Vec<u32>>()).unwrap();
+
+    let pipeline = pollster::block_on(xettacast::ComputePipeline::new(&instance, DOUBLE_SHADER.to_string(), "cs_main", vec![buffer.layout()], Some("Double"))).unwrap();
+    assert_eq!(pipeline.workgroup_size(), (64, 1, 1));
+    assert_eq!(pipeline.workgroup_count((count, 1, 1)), (16, 1, 1));
+
+    instance.dispatch(&pipeline, &[buffer.bind_group()], (count, 1, 1)).unwrap();
+    assert_eq!(pollster::block_on(buffer.read_back(&instance)).unwrap(), (0..count).map(|value| value * 2).collect::<Vec<u32>>());
+}
+
+#[test]
+fn compute_rejects_unknown_entry_point() {
+    let (_lock, instance) = match common::instance() {
+        Some(instance) => instance,
+        None => return,
+    };
+
+    let buffer = pollster::block_on(xettacast::ShaderStorageBuffer::<u32>::new_read_write(&instance, 1, Some("Values"))).unwrap();
+    assert!(pollster::block_on(xettacast::ComputePipeline::new(&instance, DOUBLE_SHADER.to_string(), "main", vec![buffer.layout()], None)).is_err());
+}
@@ -0,0 +1,150 @@
+use rand::{Rng, SeedableRng};
+
+fn overlaps(a: &xettacast::TexturePackerRegion, b: &xettacast::TexturePackerRegion) -> bool {
+    a.layer == b.layer
+        && a.x < b.x + b.width && b.x < a.x + a.width
+        && a.y < b.y + b.height && b.y < a.y + a.height
+}
+
+fn check_invariants(packer: &xettacast::TexturePacker, context: &str) {
+    let (width, height, depth) = packer.size();
+    let regions = packer.regions().map(|(name, region)| (name.clone(), region)).collect::<Vec<_>>();
+    let free = packer.free_regions().collect::<Vec<_>>();
+
+    for (name, region) in regions.iter() {
+        assert!(region.layer < depth && region.x + region.width <= width && region.y + region.height <= height,
+            "{}: region {} out of bounds: {:?}", context, name, region);
+    }
+
+    for (i, (name_a, a)) in regions.iter().enumerate() {
+        for (name_b, b) in regions.iter().skip(i + 1) {
+            assert!(!overlaps(a, b), "{}: regions {} and {} overlap: {:?} {:?}", context, name_a, name_b, a, b);
+        }
+        for space in free.iter() {
+            assert!(!overlaps(a, space), "{}: region {} overlaps free space: {:?} {:?}", context, name_a, a, space);
+        }
+    }
+
+    for (i, a) in free.iter().enumerate() {
+        for b in free.iter().skip(i + 1) {
+            assert!(!overlaps(a, b), "{}: free spaces overlap: {:?} {:?}", context, a, b);
+        }
+    }
+
+    // nothing overlaps, so any space missing from both lists has leaked
+    let area = regions.iter().map(|(_, region)| region).chain(free.iter()).map(|region| (region.width * region.height) as u64).sum::<u64>();
+    assert_eq!(area, (width * height * depth) as u64, "{}: spaces leaked", context);
+}
+
+fn random_sequence(seed: u64, format: xettacast::TexturePackerFormat) {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let mut packer = pollster::block_on(xettacast::TexturePacker::new(256, 256, 2, format)).unwrap();
+    let bpp = format.bytes_per_pixel() as usize;
+    let mut next_id = 0;
+
+    for step in 0..200 {
+        let context = format!("seed {} step {}", seed, step);
+        let names = packer.regions().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+
+        match rng.gen_range(0..6) {
+            0 | 1 => {
+                // sometimes a registered name again, which replaces its region
+                let name = if !names.is_empty() && rng.gen_bool(0.25) {
+                    names[rng.gen_range(0..names.len())].clone()
+                } else {
+                    next_id += 1;
+                    format!("item{}", next_id)
+                };
+                packer.add(&name, rng.gen_range(1..64), rng.gen_range(1..64));
+            },
+            2 => {
+                // running out of space is fine, breaking the layout is not
+                let _ = packer.pack();
+            },
+            3 if !names.is_empty() => {
+                let name = &names[rng.gen_range(0..names.len())];
+                let region = packer.region(name).unwrap();
+                let (width, height) = (rng.gen_range(1..96), rng.gen_range(1..96));
+                let data = (0..width * height * bpp).map(|_| rng.gen::<u8>()).collect::<Vec<u8>>();
+                packer.update(name, &data, width as u32, height as u32).unwrap();
+
+                if (width as u32, height as u32) == (region.width, region.height) {
+                    let layer = packer.layer_data(region.layer).unwrap();
+                    let row = region.width as usize * bpp;
+                    for y in 0..region.height as usize {
+                        let offset = ((region.y as usize + y) * 256 + region.x as usize) * bpp;
+                        assert_eq!(&layer[offset..offset + row], &data[y * row..(y + 1) * row], "{}: update mismatch", context);
+                    }
+                }
+
+                assert!(packer.update(name, &data[..data.len() - 1], width as u32, height as u32).is_err(), "{}: short buffer accepted", context);
+            },
+            4 if !names.is_empty() => {
+                let name = &names[rng.gen_range(0..names.len())];
+                packer.remove(name).unwrap();
+                assert!(packer.region(name).is_none(), "{}: removed region still registered", context);
+            },
+            _ => {
+                assert!(packer.update("missing", &[0u8; 16], 2, 2).is_err(), "{}: unknown region accepted", context);
+                assert!(packer.remove("missing").is_err(), "{}: unknown region removed", context);
+                assert!(packer.save("unused.png", 2).is_err(), "{}: out of range layer accepted", context);
+            },
+        }
+
+        check_invariants(&packer, &context);
+    }
+}
+
+#[test]
+fn random_sequences_never_overlap_r8() {
+    for seed in 0..32 {
+        random_sequence(seed, xettacast::TexturePackerFormat::R8);
+    }
+}
+
+#[test]
+fn random_sequences_never_overlap_rgba8() {
+    for seed in 0..32 {
+        random_sequence(1000 + seed, xettacast::TexturePackerFormat::Rgba8);
+    }
+}
+
+// a cache with a manifest edited to the given values, the packer itself fits in 64x64
+fn corrupt_cache(name: &str, edit: impl FnOnce(&mut serde_yaml::Value)) -> Result<xettacast::TexturePacker, String> {
+    let dir = std::env::temp_dir().join(format!("xettacast_test_cache_{}_{}", name, std::process::id()));
+    let dir = dir.to_str().unwrap();
+    let mut packer = pollster::block_on(xettacast::TexturePacker::new(64, 64, 1, xettacast::TexturePackerFormat::R8)).unwrap();
+    packer.add("item", 16, 16);
+    packer.pack().unwrap();
+    let key = packer.cache_key(&[name.as_bytes()]);
+    packer.save_cache(dir, key).unwrap();
+
+    let path = std::path::Path::new(dir).join(format!("atlas_{:016x}.yml", key));
+    let mut manifest: serde_yaml::Value = serde_yaml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    edit(&mut manifest);
+    std::fs::write(&path, serde_yaml::to_string(&manifest).unwrap()).unwrap();
+
+    let result = pollster::block_on(xettacast::TexturePacker::from_cache(dir, key));
+    std::fs::remove_dir_all(dir).unwrap();
+    result
+}
+
+#[test]
+fn overflowing_cache_is_rejected() {
+    assert!(corrupt_cache("untouched", |_| {}).is_ok());
+
+    let err = corrupt_cache("region", |manifest| {
+        manifest["regions"]["item"] = serde_yaml::to_value([u32::MAX - 8, 0, 16, 16, 0]).unwrap();
+    }).err().unwrap();
+    assert_eq!(err, "Cache contains out of bounds spaces");
+
+    let err = corrupt_cache("space", |manifest| {
+        manifest["spaces"][0] = serde_yaml::to_value([0, u32::MAX, 16, 1, 0]).unwrap();
+    }).err().unwrap();
+    assert_eq!(err, "Cache contains out of bounds spaces");
+
+    assert!(corrupt_cache("size", |manifest| {
+        manifest["width"] = u32::MAX.into();
+        manifest["height"] = u32::MAX.into();
+    }).is_err());
+}